@@ -1,11 +1,33 @@
 use serde::Deserialize;
 
+use crate::{scene_items::SceneItemTarget, transform::TransformPreset};
+
 pub enum Action {
     Recording(RecordingActionProperties),
     Streaming(StreamActionProperties),
     VirtualCamera(VirtualCameraActionProperties),
     SwitchScene(SwitchSceneProperties),
     SwitchProfile(SwitchProfileProperties),
+    RawRequest(RawRequestProperties),
+    TriggerHotkey(TriggerHotkeyProperties),
+    Screenshot(ScreenshotProperties),
+    StreamStats(StreamStatsProperties),
+    PerformanceStats(PerformanceStatsProperties),
+    SendCaption(SendCaptionProperties),
+    SetRecordDirectory(SetRecordDirectoryProperties),
+    SetStreamService(SetStreamServiceProperties),
+    SetVideoSettings(SetVideoSettingsProperties),
+    ToggleFilter(ToggleFilterProperties),
+    SetFilterSettings(SetFilterSettingsProperties),
+    SetInputSettings(SetInputSettingsProperties),
+    UpdateText(UpdateTextProperties),
+    RefreshBrowserSource(RefreshBrowserSourceProperties),
+    PressInputButton(PressInputButtonProperties),
+    SetSceneItemTransform(SetSceneItemTransformProperties),
+    SceneItemOrder(SceneItemOrderProperties),
+    SceneItemLock(SceneItemLockProperties),
+    CycleScenes(CycleScenesProperties),
+    PreviousScene(PreviousSceneProperties),
 }
 
 impl Action {
@@ -19,6 +41,42 @@ impl Action {
             "virtual_camera" => serde_json::from_value(properties).map(Action::VirtualCamera),
             "switch_scene" => serde_json::from_value(properties).map(Action::SwitchScene),
             "switch_profile" => serde_json::from_value(properties).map(Action::SwitchProfile),
+            "raw_request" => serde_json::from_value(properties).map(Action::RawRequest),
+            "trigger_hotkey" => serde_json::from_value(properties).map(Action::TriggerHotkey),
+            "screenshot" => serde_json::from_value(properties).map(Action::Screenshot),
+            "stream_stats" => serde_json::from_value(properties).map(Action::StreamStats),
+            "performance_stats" => serde_json::from_value(properties).map(Action::PerformanceStats),
+            "send_caption" => serde_json::from_value(properties).map(Action::SendCaption),
+            "set_record_directory" => {
+                serde_json::from_value(properties).map(Action::SetRecordDirectory)
+            }
+            "set_stream_service" => {
+                serde_json::from_value(properties).map(Action::SetStreamService)
+            }
+            "set_video_settings" => {
+                serde_json::from_value(properties).map(Action::SetVideoSettings)
+            }
+            "toggle_filter" => serde_json::from_value(properties).map(Action::ToggleFilter),
+            "set_filter_settings" => {
+                serde_json::from_value(properties).map(Action::SetFilterSettings)
+            }
+            "set_input_settings" => {
+                serde_json::from_value(properties).map(Action::SetInputSettings)
+            }
+            "update_text" => serde_json::from_value(properties).map(Action::UpdateText),
+            "refresh_browser_source" => {
+                serde_json::from_value(properties).map(Action::RefreshBrowserSource)
+            }
+            "press_input_button" => {
+                serde_json::from_value(properties).map(Action::PressInputButton)
+            }
+            "set_scene_item_transform" => {
+                serde_json::from_value(properties).map(Action::SetSceneItemTransform)
+            }
+            "scene_item_order" => serde_json::from_value(properties).map(Action::SceneItemOrder),
+            "scene_item_lock" => serde_json::from_value(properties).map(Action::SceneItemLock),
+            "cycle_scenes" => serde_json::from_value(properties).map(Action::CycleScenes),
+            "previous_scene" => serde_json::from_value(properties).map(Action::PreviousScene),
             _ => return None,
         })
     }
@@ -27,6 +85,11 @@ impl Action {
 #[derive(Deserialize)]
 pub struct SwitchSceneProperties {
     pub scene: Option<String>,
+    /// Show a live thumbnail of the scene as the tile icon
+    #[serde(default)]
+    pub thumbnail: bool,
+    /// Seconds between thumbnail refreshes
+    pub thumbnail_interval: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -37,6 +100,22 @@ pub struct SwitchProfileProperties {
 #[derive(Deserialize)]
 pub struct RecordingActionProperties {
     pub action: Option<RecordingAction>,
+    #[serde(flatten)]
+    pub status: OutputStatusProperties,
+    #[serde(flatten)]
+    pub disk_guard: DiskGuardProperties,
+}
+
+/// Disk space requirements for starting recordings
+#[derive(Deserialize)]
+pub struct DiskGuardProperties {
+    /// Minimum free disk space in GB required to start recording
+    pub min_disk_space: Option<f64>,
+    /// Only warn on the tile instead of refusing to start
+    #[serde(default)]
+    pub disk_space_warn_only: bool,
+    /// Stop recording when free disk space drops below this many GB
+    pub critical_disk_space: Option<f64>,
 }
 
 #[derive(Deserialize)]
@@ -47,11 +126,29 @@ pub enum RecordingAction {
     PauseResume,
     Pause,
     Resume,
+    Split,
+    AddChapter {
+        /// Template for the chapter name
+        #[serde(default)]
+        name: Option<String>,
+    },
 }
 
 #[derive(Deserialize)]
 pub struct StreamActionProperties {
     pub action: Option<StreamAction>,
+    #[serde(flatten)]
+    pub status: OutputStatusProperties,
+}
+
+/// Properties for displaying the status of an output on the tile
+#[derive(Deserialize)]
+pub struct OutputStatusProperties {
+    /// Show the elapsed time of the output as the tile label
+    #[serde(default)]
+    pub show_status: bool,
+    /// Label to show while the output is inactive
+    pub idle_label: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -72,3 +169,324 @@ pub enum VirtualCameraAction {
     Start,
     Stop,
 }
+
+#[derive(Deserialize)]
+pub struct RawRequestProperties {
+    pub mode: Option<RawRequestMode>,
+    /// Request type for a single request
+    pub request_type: Option<String>,
+    /// JSON encoded request data for a single request
+    pub request_data: Option<String>,
+    /// JSON encoded array of requests for a batch
+    pub batch: Option<String>,
+    /// Whether to stop processing a batch when a request fails
+    #[serde(default)]
+    pub halt_on_failure: bool,
+}
+
+#[derive(Deserialize)]
+pub enum RawRequestMode {
+    Single,
+    Batch,
+}
+
+#[derive(Deserialize)]
+pub struct TriggerHotkeyProperties {
+    pub mode: Option<TriggerHotkeyMode>,
+    /// Name of the hotkey to trigger
+    pub hotkey: Option<String>,
+    /// OBS key ID for key sequences (e.g OBS_KEY_F13)
+    pub key_id: Option<String>,
+    /// Modifiers for key sequences
+    #[serde(default)]
+    pub modifiers: HotkeyModifiers,
+}
+
+#[derive(Deserialize)]
+pub enum TriggerHotkeyMode {
+    Name,
+    KeySequence,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct HotkeyModifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub command: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ScreenshotProperties {
+    pub target: Option<ScreenshotTarget>,
+    /// UUID of the source when targeting a specific source
+    pub source: Option<String>,
+    /// Directory to save screenshots within
+    pub directory: Option<String>,
+    /// Template for the screenshot file name (Without extension)
+    pub file_name: Option<String>,
+    /// Image format to save the screenshot as
+    pub format: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Compression quality (0-100, -1 for default)
+    pub quality: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub enum ScreenshotTarget {
+    Program,
+    Source,
+}
+
+/// Warning thresholds for the stream health display
+#[derive(Deserialize)]
+pub struct StreamStatsProperties {
+    /// Minimum bitrate in kb/s before warning
+    pub bitrate_warning: Option<f64>,
+    /// Percentage of dropped frames before warning
+    pub dropped_frames_warning: Option<f64>,
+    /// Congestion percentage before warning
+    pub congestion_warning: Option<f64>,
+    /// CPU usage percentage before warning
+    pub cpu_warning: Option<f64>,
+    /// Label colour used when a threshold is exceeded
+    pub warning_color: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PerformanceStatsProperties {
+    pub metric: Option<PerformanceMetric>,
+    /// Seconds between refreshes
+    pub interval: Option<u64>,
+    /// Value of the metric at which the label colour changes
+    pub warning_threshold: Option<f64>,
+    /// Label colour used when the threshold is exceeded
+    pub warning_color: Option<String>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum PerformanceMetric {
+    /// Current FPS being rendered
+    Fps,
+    /// Average frame render time in milliseconds
+    RenderTime,
+    /// Percentage of frames skipped by the render thread
+    RenderSkippedFrames,
+    /// Percentage of frames skipped by the output thread
+    OutputSkippedFrames,
+    /// Memory used by OBS in MB
+    MemoryUsage,
+    /// Available disk space for recordings in GB
+    DiskSpace,
+}
+
+#[derive(Deserialize)]
+pub struct SendCaptionProperties {
+    pub mode: Option<SendCaptionMode>,
+    /// Caption text to send
+    pub text: Option<String>,
+    /// Path of the file or named pipe to read caption lines from
+    pub feed_path: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub enum SendCaptionMode {
+    /// Send the preset caption text
+    Text,
+    /// Toggle sending lines written to the feed file as captions
+    Feed,
+}
+
+#[derive(Deserialize)]
+pub struct SetRecordDirectoryProperties {
+    /// Template for the recording directory
+    pub directory: Option<String>,
+    /// Project name available to the directory template
+    pub project: Option<String>,
+    /// OBS filename formatting for recordings (e.g %CCYY-%MM-%DD %hh-%mm-%ss)
+    pub file_name_format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetStreamServiceProperties {
+    /// Name of the stream service preset from the plugin properties
+    pub preset: Option<String>,
+}
+
+/// Video settings preset, settings that are not provided are left unchanged
+#[derive(Deserialize)]
+pub struct SetVideoSettingsProperties {
+    /// Base (canvas) resolution
+    pub base_width: Option<u32>,
+    pub base_height: Option<u32>,
+    /// Output (scaled) resolution
+    pub output_width: Option<u32>,
+    pub output_height: Option<u32>,
+    /// Fractional FPS value (e.g 30000 / 1001)
+    pub fps_numerator: Option<u32>,
+    pub fps_denominator: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct ToggleFilterProperties {
+    /// UUID of the source the filter is on
+    pub source: Option<String>,
+    /// Name of the filter
+    pub filter: Option<String>,
+    pub action: Option<FilterAction>,
+    /// Show whether the filter is enabled as the tile label
+    #[serde(default)]
+    pub show_status: bool,
+    /// Label to show while the filter is enabled
+    pub enabled_label: Option<String>,
+    /// Label to show while the filter is disabled
+    pub disabled_label: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub enum FilterAction {
+    Toggle,
+    Enable,
+    Disable,
+}
+
+#[derive(Deserialize)]
+pub struct SetFilterSettingsProperties {
+    /// UUID of the source the filter is on
+    pub source: Option<String>,
+    /// Name of the filter
+    pub filter: Option<String>,
+    /// JSON object of settings to overlay onto the current filter settings
+    pub settings: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetInputSettingsProperties {
+    /// UUID of the input
+    pub input: Option<String>,
+    /// JSON object of settings to overlay onto the current input settings
+    pub settings: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTextProperties {
+    /// UUID of the text input
+    pub input: Option<String>,
+    /// Template for the text
+    pub template: Option<String>,
+    pub counter_mode: Option<CounterMode>,
+    /// Current value of the counter, updated on each press
+    #[serde(default)]
+    pub counter: i64,
+    /// Amount to increment or decrement the counter by
+    pub counter_step: Option<i64>,
+    /// Value the counter is reset to
+    pub counter_start: Option<i64>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum CounterMode {
+    /// Only update the text
+    Update,
+    Increment,
+    Decrement,
+    Reset,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshBrowserSourceProperties {
+    /// UUID of the browser input
+    pub input: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PressInputButtonProperties {
+    /// UUID of the input
+    pub input: Option<String>,
+    /// Name of the button property to press
+    pub button: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SetSceneItemTransformProperties {
+    #[serde(flatten)]
+    pub target: SceneItemTarget,
+    #[serde(flatten)]
+    pub transform: TransformPreset,
+    /// Animate from the current transform instead of jumping to it
+    #[serde(default)]
+    pub animate: bool,
+    /// Duration of the animation in milliseconds
+    pub animation_duration: Option<u64>,
+    pub animation_easing: Option<Easing>,
+    /// Maximum number of transform updates per second while animating
+    pub animation_fps: Option<u32>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+#[derive(Deserialize)]
+pub struct SceneItemOrderProperties {
+    #[serde(flatten)]
+    pub target: SceneItemTarget,
+    pub action: Option<SceneItemOrderAction>,
+}
+
+#[derive(Deserialize)]
+pub enum SceneItemOrderAction {
+    /// Move one position towards the top
+    Up,
+    /// Move one position towards the bottom
+    Down,
+    Top,
+    Bottom,
+}
+
+#[derive(Deserialize)]
+pub struct SceneItemLockProperties {
+    #[serde(flatten)]
+    pub target: SceneItemTarget,
+    pub action: Option<SceneItemLockAction>,
+}
+
+#[derive(Deserialize)]
+pub enum SceneItemLockAction {
+    Toggle,
+    Lock,
+    Unlock,
+}
+
+#[derive(Deserialize)]
+pub struct CycleScenesProperties {
+    /// UUIDs of the scenes to cycle through in order
+    #[serde(default)]
+    pub scenes: Vec<String>,
+    pub mode: Option<CycleScenesMode>,
+    /// Advance on a timer until pressed again instead of on each press
+    #[serde(default)]
+    pub auto_advance: bool,
+    /// Seconds between scenes when auto advancing
+    pub interval: Option<u64>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum CycleScenesMode {
+    /// Start again from the first scene after the last
+    Wrap,
+    /// Reverse direction at the first and last scenes
+    PingPong,
+}
+
+#[derive(Deserialize)]
+pub struct PreviousSceneProperties {
+    /// Number of scenes that can be returned to in a row
+    pub depth: Option<usize>,
+}
@@ -0,0 +1,140 @@
+//! Caption feeds for stream captions
+//!
+//! External processes (Speech to text tools, etc) can append caption lines
+//! to a file or write them to a named pipe, while a feed is active each new
+//! line is sent to OBS as a stream caption
+
+use std::{io::SeekFrom, path::PathBuf, rc::Rc, time::Duration};
+
+use tilepad_plugin_sdk::{DeviceIndicator, PluginSessionHandle, TileInteractionContext, tracing};
+use tokio::{
+    fs::{self, File},
+    io::{AsyncBufReadExt, AsyncSeekExt, BufReader},
+    time::sleep,
+};
+
+use crate::{
+    indicator::display_indicator,
+    state::{ObsError, State},
+    tile_tasks::TileTasks,
+};
+
+/// Time to wait before checking for new lines when the end of the feed
+/// has been reached
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Starts the caption feed for the tile, or stops it if the tile
+/// already has an active feed
+pub fn toggle_caption_feed(
+    tasks: &TileTasks,
+    state: Rc<State>,
+    session: PluginSessionHandle,
+    ctx: TileInteractionContext,
+    properties: serde_json::Value,
+    path: PathBuf,
+) {
+    let task = watch_caption_feed(state, session.clone(), ctx.clone(), path.clone());
+
+    if !tasks.toggle(ctx.tile_id, properties, task) {
+        tracing::debug!(?path, "stopped caption feed");
+        display_indicator(&session, &ctx, DeviceIndicator::Success);
+    }
+}
+
+/// Sends caption text over the stream output, captions can only be
+/// sent while streaming
+pub async fn send_caption(client: &obws::Client, text: &str) -> Result<(), ObsError> {
+    if let Err(cause) = client.streaming().send_caption(text).await {
+        tracing::error!(?cause, "failed to send stream caption");
+        return Err(cause);
+    }
+
+    Ok(())
+}
+
+/// Follows the file or named pipe at `path` sending each new line as a
+/// stream caption
+async fn watch_caption_feed(
+    state: Rc<State>,
+    session: PluginSessionHandle,
+    ctx: TileInteractionContext,
+    path: PathBuf,
+) {
+    // Opening a named pipe waits for a writer to connect
+    let file = match File::open(&path).await {
+        Ok(value) => value,
+        Err(cause) => {
+            tracing::error!(?cause, ?path, "failed to open caption feed");
+            display_indicator(&session, &ctx, DeviceIndicator::Error);
+            return;
+        }
+    };
+
+    let mut reader = BufReader::new(file);
+
+    // Existing lines in regular files are not captions for this feed
+    let mut position = match fs::metadata(&path).await {
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        _ => 0,
+    };
+
+    if position > 0
+        && let Err(cause) = reader.seek(SeekFrom::Start(position)).await
+    {
+        tracing::error!(?cause, ?path, "failed to seek caption feed");
+        return;
+    }
+
+    tracing::debug!(?path, "started caption feed");
+    display_indicator(&session, &ctx, DeviceIndicator::Success);
+
+    let mut line = String::new();
+
+    loop {
+        let read = match reader.read_line(&mut line).await {
+            Ok(value) => value,
+            Err(cause) => {
+                tracing::error!(?cause, ?path, "failed to read caption feed");
+                return;
+            }
+        };
+
+        if read == 0 {
+            // Start again from the beginning if the file was truncated
+            if let Ok(metadata) = fs::metadata(&path).await
+                && metadata.is_file()
+                && metadata.len() < position
+            {
+                line.clear();
+                position = 0;
+
+                if let Err(cause) = reader.seek(SeekFrom::Start(0)).await {
+                    tracing::error!(?cause, ?path, "failed to seek caption feed");
+                    return;
+                }
+            }
+
+            sleep(POLL_INTERVAL).await;
+            continue;
+        }
+
+        position += read as u64;
+
+        // Wait for the rest of a partially written line
+        if !line.ends_with('\n') {
+            continue;
+        }
+
+        let text = line.trim();
+        if !text.is_empty() {
+            // Failures are logged, the feed continues with the next line
+            let text = text.to_string();
+            _ = state
+                .clone()
+                .execute_with_client(async move |client| send_caption(client, &text).await)
+                .await;
+        }
+
+        line.clear();
+    }
+}
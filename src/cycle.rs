@@ -0,0 +1,180 @@
+//! Scene cycling
+//!
+//! Tiles step through an ordered list of scenes on each press, or when
+//! auto advancing, on a timer until the tile is pressed again
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+
+use obws::requests::scenes::SceneId;
+use tilepad_plugin_sdk::{TileId, tracing};
+use tokio::{task::spawn_local, time::sleep};
+use uuid::Uuid;
+
+use crate::{
+    action::CycleScenesMode,
+    state::{ObsError, State},
+    tile_tasks::TileTasks,
+};
+
+/// Scene cycle positions for each tile
+#[derive(Default, Clone)]
+pub struct SceneCycles {
+    positions: Rc<RefCell<HashMap<TileId, CyclePosition>>>,
+}
+
+/// Ordered list of scenes to cycle through
+pub struct SceneCycle {
+    pub scenes: Vec<Uuid>,
+    pub mode: CycleScenesMode,
+}
+
+/// Position of a tile within its scene cycle
+#[derive(Default, Clone, Copy)]
+struct CyclePosition {
+    /// Index of the last scene switched to
+    index: Option<usize>,
+    /// Whether a ping pong cycle is moving backwards through the list
+    reverse: bool,
+}
+
+impl SceneCycles {
+    /// Switches to the next scene in the cycle
+    pub fn advance(&self, state: Rc<State>, tile_id: TileId, cycle: SceneCycle) {
+        let cycles = self.clone();
+        spawn_local(async move {
+            _ = cycles.next_scene(state, tile_id, &cycle).await;
+        });
+    }
+
+    /// Starts switching to the next scene in the cycle every `interval`,
+    /// or stops if the tile is already auto advancing
+    pub fn toggle_auto_advance(
+        &self,
+        tasks: &TileTasks,
+        state: Rc<State>,
+        tile_id: TileId,
+        properties: serde_json::Value,
+        cycle: SceneCycle,
+        interval: Duration,
+    ) {
+        let cycles = self.clone();
+        let task = async move {
+            loop {
+                // Stop when disconnected or switching fails
+                match cycles.next_scene(state.clone(), tile_id, &cycle).await {
+                    Ok(Some(_)) => {}
+                    _ => return,
+                }
+
+                sleep(interval).await;
+            }
+        };
+
+        if !tasks.toggle(tile_id, properties, task) {
+            tracing::debug!("stopped scene cycle");
+        }
+    }
+
+    /// Switches the program scene to the next scene in the `cycle`
+    async fn next_scene(
+        &self,
+        state: Rc<State>,
+        tile_id: TileId,
+        cycle: &SceneCycle,
+    ) -> Result<Option<()>, ObsError> {
+        if cycle.scenes.is_empty() {
+            return Ok(None);
+        }
+
+        let mut position = self
+            .positions
+            .borrow()
+            .get(&tile_id)
+            .copied()
+            .unwrap_or_default();
+        let scenes = cycle.scenes.clone();
+        let mode = cycle.mode;
+
+        let scene = state
+            .execute_with_client(async move |client| {
+                let current = match client.scenes().current_program_scene().await {
+                    Ok(value) => value,
+                    Err(cause) => {
+                        tracing::error!(?cause, "failed to get current scene");
+                        return Err(cause);
+                    }
+                };
+
+                position.sync(&scenes, current.id.uuid);
+                let index = position.advance(scenes.len(), mode);
+
+                if let Err(cause) = client
+                    .scenes()
+                    .set_current_program_scene(SceneId::Uuid(scenes[index]))
+                    .await
+                {
+                    tracing::error!(?cause, "failed to set current scene");
+                    return Err(cause);
+                }
+
+                Ok(position)
+            })
+            .await?;
+
+        Ok(scene.map(|position| {
+            self.positions.borrow_mut().insert(tile_id, position);
+        }))
+    }
+}
+
+impl CyclePosition {
+    /// Moves the position to the current scene when it was switched to
+    /// outside of the cycle
+    fn sync(&mut self, scenes: &[Uuid], current: Uuid) {
+        // Scenes can appear in the cycle more than once, keep the current
+        // position if it's already on the current scene
+        if self
+            .index
+            .is_some_and(|index| scenes.get(index) == Some(&current))
+        {
+            return;
+        }
+
+        if let Some(index) = scenes.iter().position(|scene| *scene == current) {
+            self.index = Some(index);
+        }
+    }
+
+    /// Moves to the next position in a cycle of `len` scenes, returning
+    /// the new index
+    fn advance(&mut self, len: usize, mode: CycleScenesMode) -> usize {
+        let last = len - 1;
+
+        // Index may be past the end when scenes were removed from the cycle
+        let index = match (self.index.map(|index| index.min(last)), mode) {
+            // Start from the beginning of the cycle
+            (None, _) => {
+                self.reverse = false;
+                0
+            }
+            (Some(index), CycleScenesMode::Wrap) if index >= last => 0,
+            (Some(index), CycleScenesMode::Wrap) => index + 1,
+            (Some(index), CycleScenesMode::PingPong) => {
+                // Turn around at either end of the cycle
+                if index >= last {
+                    self.reverse = true;
+                } else if index == 0 {
+                    self.reverse = false;
+                }
+
+                match self.reverse {
+                    true => index.saturating_sub(1),
+                    false => index + 1,
+                }
+            }
+        };
+
+        self.index = Some(index);
+        index
+    }
+}
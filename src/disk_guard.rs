@@ -0,0 +1,298 @@
+//! Disk space checks for recordings
+//!
+//! Recordings can be refused when there isn't enough free disk space
+//! to start and stopped automatically when space becomes critically low
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+
+use tilepad_plugin_sdk::{
+    DeviceIndicator, PluginSessionHandle, TileId, TileInteractionContext, TileModel, tracing,
+};
+use tokio::{
+    task::{JoinHandle, spawn_local},
+    time::sleep,
+};
+
+use crate::{
+    action::{Action, DiskGuardProperties},
+    indicator::display_indicator,
+    monitor::{StatusOutput, wait_for_output_change},
+    state::{ObsError, State},
+    tile_tasks::get_hidden_tile_properties,
+};
+
+/// Time between disk space checks while recording
+const CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Guard that stops the recording when disk space is critically low,
+/// recordings are guarded no matter how they were started
+#[derive(Default, Clone)]
+pub struct DiskGuard {
+    /// Critical disk space configured on each recording tile
+    thresholds: Rc<RefCell<HashMap<TileId, Threshold>>>,
+    /// Recording tile last pressed, low disk space is indicated on it
+    last_tile: Rc<RefCell<Option<TileInteractionContext>>>,
+    /// Task watching the recording state
+    task: Rc<RefCell<Option<JoinHandle<()>>>>,
+}
+
+/// Critical disk space configured on a tile
+struct Threshold {
+    /// Free disk space in GB to stop recording below
+    critical_disk_space: f64,
+    /// Whether the tile was visible at the last update
+    visible: bool,
+}
+
+impl DiskGuard {
+    /// Updates the critical disk space from the visible tiles, tiles that
+    /// are no longer visible are checked in case they were removed
+    pub fn update_tiles(
+        &self,
+        state: &Rc<State>,
+        session: &PluginSessionHandle,
+        tiles: &[TileModel],
+    ) {
+        for tile in tiles {
+            // Tiles changed to other actions no longer guard recordings
+            let properties = serde_json::Value::Object(tile.properties.clone());
+            let critical_disk_space = match Action::from_action(&tile.action_id, properties) {
+                Some(Ok(Action::Recording(properties))) => {
+                    properties.disk_guard.critical_disk_space
+                }
+                _ => None,
+            };
+
+            self.configure(state, session, tile.id, critical_disk_space);
+        }
+
+        for (tile_id, threshold) in self.thresholds.borrow_mut().iter_mut() {
+            if !threshold.visible || tiles.iter().any(|tile| tile.id.eq(tile_id)) {
+                continue;
+            }
+
+            threshold.visible = false;
+            spawn_local(self.clone().check_hidden(session.clone(), *tile_id));
+        }
+    }
+
+    /// Updates the critical disk space after a recording tile is pressed
+    pub fn update_pressed(
+        &self,
+        state: &Rc<State>,
+        session: &PluginSessionHandle,
+        ctx: TileInteractionContext,
+        critical_disk_space: Option<f64>,
+    ) {
+        self.configure(state, session, ctx.tile_id, critical_disk_space);
+        self.last_tile.replace(Some(ctx));
+    }
+
+    /// Sets the critical disk space for a tile, starting the guard when
+    /// the first tile enables it
+    fn configure(
+        &self,
+        state: &Rc<State>,
+        session: &PluginSessionHandle,
+        tile_id: TileId,
+        critical_disk_space: Option<f64>,
+    ) {
+        {
+            let mut thresholds = self.thresholds.borrow_mut();
+            match critical_disk_space {
+                Some(critical_disk_space) => thresholds.insert(
+                    tile_id,
+                    Threshold {
+                        critical_disk_space,
+                        visible: true,
+                    },
+                ),
+                None => thresholds.remove(&tile_id),
+            };
+
+            if thresholds.is_empty() {
+                return;
+            }
+        }
+
+        let mut task = self.task.borrow_mut();
+        if task.is_none() {
+            *task = Some(spawn_local(guard_recording(
+                state.clone(),
+                session.clone(),
+                self.clone(),
+            )));
+        }
+    }
+
+    /// Removes the critical disk space of a tile that is no longer visible
+    /// if the tile was removed or no longer guards recordings
+    async fn check_hidden(self, session: PluginSessionHandle, tile_id: TileId) {
+        let critical_disk_space = get_hidden_tile_properties(&session, tile_id)
+            .await
+            .and_then(|properties| {
+                let properties = serde_json::Value::Object(properties);
+                match Action::from_action("recording", properties) {
+                    Some(Ok(Action::Recording(properties))) => {
+                        properties.disk_guard.critical_disk_space
+                    }
+                    _ => None,
+                }
+            });
+
+        let mut thresholds = self.thresholds.borrow_mut();
+
+        // Tile became visible again while checking
+        if thresholds
+            .get(&tile_id)
+            .is_none_or(|threshold| threshold.visible)
+        {
+            return;
+        }
+
+        match critical_disk_space {
+            Some(critical_disk_space) => thresholds.insert(
+                tile_id,
+                Threshold {
+                    critical_disk_space,
+                    visible: false,
+                },
+            ),
+            None => thresholds.remove(&tile_id),
+        };
+    }
+
+    /// Highest critical disk space of all the tiles
+    fn critical_disk_space(&self) -> Option<f64> {
+        self.thresholds
+            .borrow()
+            .values()
+            .map(|threshold| threshold.critical_disk_space)
+            .reduce(f64::max)
+    }
+}
+
+/// Checks the available disk space before starting a recording, returns
+/// whether the recording should be started
+pub async fn check_before_start(
+    client: &obws::Client,
+    session: &PluginSessionHandle,
+    ctx: &TileInteractionContext,
+    properties: &DiskGuardProperties,
+) -> Result<bool, ObsError> {
+    let min_disk_space = match properties.min_disk_space {
+        Some(value) => value,
+        None => return Ok(true),
+    };
+
+    let stats = match client.general().stats().await {
+        Ok(value) => value,
+        Err(cause) => {
+            tracing::error!(?cause, "failed to get stats");
+            return Err(cause);
+        }
+    };
+
+    let available = stats.available_disk_space / 1024.0;
+    if available >= min_disk_space {
+        return Ok(true);
+    }
+
+    if properties.disk_space_warn_only {
+        tracing::warn!(?available, ?min_disk_space, "low disk space for recording");
+        display_indicator(session, ctx, DeviceIndicator::Warning);
+        return Ok(true);
+    }
+
+    tracing::warn!(
+        ?available,
+        ?min_disk_space,
+        "not enough disk space to start recording"
+    );
+    display_indicator(session, ctx, DeviceIndicator::Error);
+
+    Ok(false)
+}
+
+enum GuardStatus {
+    /// Recording is active with enough disk space
+    Recording,
+    /// Recording is no longer active
+    Stopped,
+    /// Recording was stopped due to low disk space
+    LowDiskSpace,
+}
+
+/// Checks the available disk space whenever a recording is active,
+/// stopping the recording if it drops below the critical disk space
+async fn guard_recording(state: Rc<State>, session: PluginSessionHandle, guard: DiskGuard) {
+    let mut events = state.subscribe_events();
+
+    loop {
+        let critical_disk_space = match guard.critical_disk_space() {
+            Some(value) => value,
+            None => {
+                wait_for_output_change(&mut events, StatusOutput::Recording).await;
+                continue;
+            }
+        };
+
+        let result = state
+            .clone()
+            .execute_with_client(async move |client| {
+                check_disk_space(client, critical_disk_space).await
+            })
+            .await;
+
+        match result {
+            Ok(Some(GuardStatus::Recording)) => sleep(CHECK_INTERVAL).await,
+
+            Ok(Some(GuardStatus::LowDiskSpace)) => {
+                if let Some(ctx) = guard.last_tile.borrow().as_ref() {
+                    display_indicator(&session, ctx, DeviceIndicator::Warning);
+                }
+
+                wait_for_output_change(&mut events, StatusOutput::Recording).await;
+            }
+
+            // Recording stopped or not connected, checking resumes when
+            // a recording starts or the connection is restored
+            Ok(Some(GuardStatus::Stopped)) | Ok(None) => {
+                wait_for_output_change(&mut events, StatusOutput::Recording).await;
+            }
+
+            // Try again later if checking failed
+            Err(cause) => {
+                tracing::error!(?cause, "failed to check disk space for recording");
+                sleep(CHECK_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Stops the recording if the available disk space is below the
+/// `critical_disk_space`
+async fn check_disk_space(
+    client: &obws::Client,
+    critical_disk_space: f64,
+) -> Result<GuardStatus, ObsError> {
+    let status = client.recording().status().await?;
+    if !status.active {
+        return Ok(GuardStatus::Stopped);
+    }
+
+    let stats = client.general().stats().await?;
+    let available = stats.available_disk_space / 1024.0;
+    if available >= critical_disk_space {
+        return Ok(GuardStatus::Recording);
+    }
+
+    tracing::warn!(
+        ?available,
+        ?critical_disk_space,
+        "disk space critically low, stopping recording"
+    );
+
+    client.recording().stop().await?;
+    Ok(GuardStatus::LowDiskSpace)
+}
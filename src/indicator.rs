@@ -0,0 +1,43 @@
+//! Indicators shown on tiles after they are interacted with
+
+use std::rc::Rc;
+
+use tilepad_plugin_sdk::{DeviceIndicator, PluginSessionHandle, TileInteractionContext};
+use tokio::task::spawn_local;
+
+use crate::state::{ObsError, State};
+
+/// Duration in milliseconds to show tile indicators for
+pub const INDICATOR_DURATION: u32 = 2000;
+
+/// Shows an indicator on the tile that was interacted with
+pub fn display_indicator(
+    session: &PluginSessionHandle,
+    ctx: &TileInteractionContext,
+    indicator: DeviceIndicator,
+) {
+    _ = session.display_indicator(ctx.device_id, ctx.tile_id, indicator, INDICATOR_DURATION);
+}
+
+/// Runs a tile action with the client, showing a success indicator on the
+/// tile when the action returns `Ok(true)` and an error indicator when it
+/// fails, is refused (`Ok(false)`) or OBS is not connected
+pub fn run_with_indicator<F>(
+    state: Rc<State>,
+    session: &PluginSessionHandle,
+    ctx: TileInteractionContext,
+    action: F,
+) where
+    F: for<'a> AsyncFnOnce(&'a mut obws::Client) -> Result<bool, ObsError>,
+    F: 'static,
+{
+    let session = session.clone();
+    spawn_local(async move {
+        let indicator = match state.execute_with_client(action).await {
+            Ok(Some(true)) => DeviceIndicator::Success,
+            _ => DeviceIndicator::Error,
+        };
+
+        display_indicator(&session, &ctx, indicator);
+    });
+}
@@ -3,9 +3,19 @@ use tilepad_plugin_sdk::{setup_tracing, start_plugin};
 use tokio::task::LocalSet;
 
 mod action;
+mod captions;
+mod cycle;
+mod disk_guard;
+mod indicator;
 mod messages;
+mod monitor;
 mod plugin;
+mod raw;
+mod scene_items;
 mod state;
+mod template;
+mod tile_tasks;
+mod transform;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
@@ -1,4 +1,7 @@
-use crate::{state::Auth, state::ClientState};
+use crate::{
+    state::{Auth, ClientState, StreamServicePreset},
+    transform::TransformPreset,
+};
 use serde::{Deserialize, Serialize};
 
 /// Messages from the inspector
@@ -8,16 +11,112 @@ pub enum InspectorMessageIn {
     GetClientState,
     GetProfiles,
     GetScenes,
-    Connect { auth: Auth },
+    GetHotkeys,
+    GetSources,
+    GetRecordDirectory,
+    GetStreamServices,
+    SaveStreamService {
+        preset: StreamServicePresetInput,
+    },
+    DeleteStreamService {
+        name: String,
+    },
+    GetVideoSettings,
+    GetSourceFilters {
+        source: String,
+    },
+    GetFilterSettings {
+        source: String,
+        filter: String,
+    },
+    GetInputs {
+        /// Unversioned input kinds to include, all inputs when empty
+        #[serde(default)]
+        kinds: Vec<String>,
+    },
+    GetInputSettings {
+        input: String,
+    },
+    GetInputButtons {
+        input: String,
+        /// Unversioned kind of the input, from the [InputOption]
+        kind: String,
+    },
+    GetSceneItems {
+        scene: String,
+    },
+    GetSceneItemTransform {
+        scene: String,
+        item: i64,
+    },
+    Connect {
+        auth: Auth,
+    },
 }
 
 /// Messages to the inspector
 #[derive(Serialize)]
 #[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum InspectorMessageOut {
-    ClientState { state: ClientState },
-    Profiles { profiles: Vec<SelectOption> },
-    Scenes { scenes: Vec<SelectOption> },
+    ClientState {
+        state: ClientState,
+    },
+    Profiles {
+        profiles: Vec<SelectOption>,
+    },
+    Scenes {
+        scenes: Vec<SelectOption>,
+    },
+    Hotkeys {
+        hotkeys: Vec<SelectOption>,
+    },
+    Sources {
+        sources: Vec<SelectOption>,
+    },
+    RecordDirectory {
+        directory: String,
+        file_name_format: Option<String>,
+    },
+    StreamServices {
+        presets: Vec<StreamServicePresetInfo>,
+    },
+    SourceFilters {
+        source: String,
+        filters: Vec<SelectOption>,
+    },
+    FilterSettings {
+        source: String,
+        filter: String,
+        settings: serde_json::Value,
+    },
+    Inputs {
+        inputs: Vec<InputOption>,
+    },
+    InputSettings {
+        input: String,
+        settings: serde_json::Value,
+    },
+    InputButtons {
+        input: String,
+        buttons: Vec<SelectOption>,
+    },
+    SceneItems {
+        scene: String,
+        items: Vec<SelectOption>,
+    },
+    SceneItemTransform {
+        scene: String,
+        item: i64,
+        transform: TransformPreset,
+    },
+    VideoSettings {
+        base_width: u32,
+        base_height: u32,
+        output_width: u32,
+        output_height: u32,
+        fps_numerator: u32,
+        fps_denominator: u32,
+    },
 }
 
 /// Option for a select dropdown menu
@@ -26,3 +125,66 @@ pub struct SelectOption {
     pub label: String,
     pub value: String,
 }
+
+/// Input option for a select dropdown menu
+#[derive(Serialize)]
+pub struct InputOption {
+    pub label: String,
+    pub value: String,
+    /// Unversioned kind of the input
+    pub kind: String,
+}
+
+/// Stream service preset from the inspector, the key is only
+/// provided when it has been changed
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StreamServicePresetInput {
+    pub name: String,
+    pub service_type: String,
+    pub service: Option<String>,
+    pub server: String,
+    pub key: Option<String>,
+}
+
+/// Stream service preset for the inspector with the key masked
+#[derive(Serialize)]
+pub struct StreamServicePresetInfo {
+    pub name: String,
+    pub service_type: String,
+    pub service: Option<String>,
+    pub server: String,
+    /// Masked stream key, empty when no key is set
+    pub key: String,
+}
+
+impl From<&StreamServicePreset> for StreamServicePresetInfo {
+    fn from(value: &StreamServicePreset) -> Self {
+        Self {
+            name: value.name.clone(),
+            service_type: value.service_type.clone(),
+            service: value.service.clone(),
+            server: value.server.clone(),
+            key: mask_key(&value.key),
+        }
+    }
+}
+
+/// Masks a stream key only keeping the last few characters visible, the
+/// mask has a fixed width so the length of the key isn't revealed
+fn mask_key(key: &str) -> String {
+    const MASK: &str = "********";
+    const VISIBLE: usize = 4;
+
+    let length = key.chars().count();
+    if length == 0 {
+        return String::new();
+    }
+
+    // Short keys are masked entirely
+    if length <= VISIBLE * 2 {
+        return MASK.to_string();
+    }
+
+    let visible: String = key.chars().skip(length - VISIBLE).collect();
+    format!("{MASK}{visible}")
+}
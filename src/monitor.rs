@@ -0,0 +1,726 @@
+//! Background tasks that keep the currently visible tiles updated
+//! with live information from OBS
+//!
+//! Tasks only run while their tile is visible on a device and are
+//! restarted whenever the tile properties change
+
+use std::{cell::Cell, collections::HashMap, rc::Rc, time::Duration};
+
+use obws::{
+    events::Event,
+    requests::sources::{SourceId, TakeScreenshot},
+    responses::general::Stats,
+};
+use tilepad_plugin_sdk::{
+    JsonObject, PluginSessionHandle, TileConfig, TileIcon, TileId, TileLabel, TileModel,
+};
+use tokio::{
+    sync::broadcast::{self, error::RecvError},
+    task::{JoinHandle, spawn_local},
+    time::{Instant, sleep, sleep_until},
+};
+use uuid::Uuid;
+
+use crate::{
+    action::{
+        Action, OutputStatusProperties, PerformanceMetric, PerformanceStatsProperties,
+        StreamStatsProperties, ToggleFilterProperties,
+    },
+    state::{ClientEvent, ObsError, State},
+    template,
+};
+
+/// Width of scene thumbnails, height is scaled to match the aspect ratio
+const THUMBNAIL_WIDTH: u32 = 192;
+
+/// Image format and quality used for scene thumbnails
+const THUMBNAIL_FORMAT: &str = "jpg";
+const THUMBNAIL_QUALITY: i32 = 60;
+
+/// Default and minimum number of seconds between thumbnail refreshes
+const DEFAULT_THUMBNAIL_INTERVAL: u64 = 5;
+const MIN_THUMBNAIL_INTERVAL: u64 = 1;
+
+/// Minimum time between any two thumbnail requests across all tiles
+const THUMBNAIL_REQUEST_GAP: Duration = Duration::from_millis(150);
+
+/// Time between output status updates while the output is active
+const OUTPUT_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default stream health warning thresholds (percentages)
+const DEFAULT_DROPPED_FRAMES_WARNING: f64 = 1.0;
+const DEFAULT_CONGESTION_WARNING: f64 = 50.0;
+const DEFAULT_CPU_WARNING: f64 = 80.0;
+
+/// Default and minimum number of seconds between performance stat refreshes
+const DEFAULT_PERFORMANCE_INTERVAL: u64 = 2;
+const MIN_PERFORMANCE_INTERVAL: u64 = 1;
+
+/// Default labels for the enabled state of filters
+const DEFAULT_ENABLED_LABEL: &str = "ON";
+const DEFAULT_DISABLED_LABEL: &str = "OFF";
+
+/// Default label colour when a warning threshold is exceeded
+const DEFAULT_WARNING_COLOR: &str = "#ff4d4d";
+
+pub struct TileMonitor {
+    /// Currently running tasks for each visible tile
+    tasks: HashMap<TileId, TileTask>,
+
+    /// Appearance of tiles from before their task changed it, tasks
+    /// restarted later use this instead of the labels or icons that
+    /// the previous task wrote
+    originals: HashMap<TileId, TileConfig>,
+
+    /// Rate limiter shared between all thumbnail tasks
+    thumbnail_limiter: Rc<RateLimiter>,
+}
+
+struct TileTask {
+    /// Properties the task was started with
+    properties: JsonObject,
+    /// Handle to the running task
+    handle: JoinHandle<()>,
+}
+
+impl Default for TileMonitor {
+    fn default() -> Self {
+        Self {
+            tasks: Default::default(),
+            originals: Default::default(),
+            thumbnail_limiter: Rc::new(RateLimiter::new(THUMBNAIL_REQUEST_GAP)),
+        }
+    }
+}
+
+impl TileMonitor {
+    /// Update the running tasks to match the currently visible `tiles`
+    pub fn update(
+        &mut self,
+        state: &Rc<State>,
+        session: &PluginSessionHandle,
+        tiles: Vec<TileModel>,
+    ) {
+        // Stop tasks for tiles that are no longer visible
+        self.tasks.retain(|tile_id, task| {
+            let visible = tiles.iter().any(|tile| tile.id.eq(tile_id));
+            if !visible {
+                task.handle.abort();
+            }
+
+            visible
+        });
+
+        for tile in tiles {
+            if let Some(task) = self.tasks.get(&tile.id) {
+                // Task is already running with the current properties
+                if task.properties == tile.properties {
+                    continue;
+                }
+
+                if let Some(task) = self.tasks.remove(&tile.id) {
+                    task.handle.abort();
+                }
+            }
+
+            // Tiles that had a task may already show labels or icons it wrote
+            let original = self.originals.remove(&tile.id);
+            let config = original.clone().unwrap_or_else(|| tile.config.clone());
+
+            let handle = match self.spawn_task(state, session, &tile, &config) {
+                Some(value) => value,
+                None => {
+                    if let Some(original) = &original {
+                        reset_tile(session, &tile, original);
+                    }
+                    continue;
+                }
+            };
+
+            self.originals.insert(tile.id, config);
+            self.tasks.insert(
+                tile.id,
+                TileTask {
+                    properties: tile.properties,
+                    handle,
+                },
+            );
+        }
+    }
+
+    /// Spawns the background task for a tile if its action requires one,
+    /// `config` is the appearance of the tile before any task changed it
+    fn spawn_task(
+        &self,
+        state: &Rc<State>,
+        session: &PluginSessionHandle,
+        tile: &TileModel,
+        config: &TileConfig,
+    ) -> Option<JoinHandle<()>> {
+        let properties = serde_json::Value::Object(tile.properties.clone());
+        let action = Action::from_action(&tile.action_id, properties)?.ok()?;
+
+        match action {
+            Action::SwitchScene(properties) if properties.thumbnail => {
+                let scene: Uuid = properties.scene?.parse().ok()?;
+                let interval = properties
+                    .thumbnail_interval
+                    .unwrap_or(DEFAULT_THUMBNAIL_INTERVAL)
+                    .max(MIN_THUMBNAIL_INTERVAL);
+
+                Some(spawn_local(scene_thumbnail(
+                    state.clone(),
+                    session.clone(),
+                    self.thumbnail_limiter.clone(),
+                    tile.id,
+                    scene,
+                    Duration::from_secs(interval),
+                )))
+            }
+            Action::Recording(properties) if properties.status.show_status => {
+                Some(spawn_output_status(
+                    state,
+                    session,
+                    tile.id,
+                    config,
+                    StatusOutput::Recording,
+                    properties.status,
+                ))
+            }
+            Action::Streaming(properties) if properties.status.show_status => {
+                Some(spawn_output_status(
+                    state,
+                    session,
+                    tile.id,
+                    config,
+                    StatusOutput::Streaming,
+                    properties.status,
+                ))
+            }
+            Action::StreamStats(properties) => Some(spawn_local(stream_stats(
+                state.clone(),
+                session.clone(),
+                tile.id,
+                config.label.clone(),
+                properties,
+            ))),
+            Action::PerformanceStats(properties) => {
+                let metric = properties.metric?;
+                let interval = properties
+                    .interval
+                    .unwrap_or(DEFAULT_PERFORMANCE_INTERVAL)
+                    .max(MIN_PERFORMANCE_INTERVAL);
+
+                Some(spawn_local(performance_stats(
+                    state.clone(),
+                    session.clone(),
+                    tile.id,
+                    config.label.clone(),
+                    metric,
+                    Duration::from_secs(interval),
+                    properties,
+                )))
+            }
+            Action::ToggleFilter(properties) if properties.show_status => {
+                let source: Uuid = properties.source.as_deref()?.parse().ok()?;
+                let filter = properties.filter.clone()?;
+
+                Some(spawn_local(filter_status(
+                    state.clone(),
+                    session.clone(),
+                    tile.id,
+                    config.label.clone(),
+                    source,
+                    filter,
+                    properties,
+                )))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn spawn_output_status(
+    state: &Rc<State>,
+    session: &PluginSessionHandle,
+    tile_id: TileId,
+    config: &TileConfig,
+    output: StatusOutput,
+    properties: OutputStatusProperties,
+) -> JoinHandle<()> {
+    spawn_local(output_status(
+        state.clone(),
+        session.clone(),
+        tile_id,
+        config.label.clone(),
+        output,
+        properties.idle_label.unwrap_or_default(),
+    ))
+}
+
+/// Restores the appearance of a tile after its task is stopped
+fn reset_tile(session: &PluginSessionHandle, tile: &TileModel, original: &TileConfig) {
+    match tile.action_id.as_str() {
+        "switch_scene" => {
+            // Icon from before thumbnails, which may be a custom icon
+            _ = session.set_tile_icon(tile.id, original.icon.clone());
+        }
+        "recording" | "streaming" | "stream_stats" | "performance_stats" | "toggle_filter" => {
+            // Label text and style are restored to what the user had set
+            _ = session.set_tile_label(tile.id, original.label.clone());
+        }
+        _ => {}
+    }
+}
+
+/// Periodically replaces the tile icon with a screenshot of the scene
+async fn scene_thumbnail(
+    state: Rc<State>,
+    session: PluginSessionHandle,
+    limiter: Rc<RateLimiter>,
+    tile_id: TileId,
+    scene: Uuid,
+    interval: Duration,
+) {
+    let mut last_image: Option<String> = None;
+
+    loop {
+        limiter.acquire().await;
+
+        let image = state
+            .clone()
+            .execute_with_client(async move |client| {
+                client
+                    .sources()
+                    .take_screenshot(TakeScreenshot {
+                        source: SourceId::Uuid(scene),
+                        format: THUMBNAIL_FORMAT,
+                        width: Some(THUMBNAIL_WIDTH),
+                        height: None,
+                        compression_quality: Some(THUMBNAIL_QUALITY),
+                    })
+                    .await
+            })
+            .await;
+
+        // Only update the icon when the image has changed
+        if let Ok(Some(image)) = image
+            && last_image.as_ref() != Some(&image)
+        {
+            _ = session.set_tile_icon(tile_id, TileIcon::Url { src: image.clone() });
+            last_image = Some(image);
+        }
+
+        sleep(interval).await;
+    }
+}
+
+/// Output that a status is displayed for
+#[derive(Clone, Copy)]
+pub enum StatusOutput {
+    Recording,
+    Streaming,
+}
+
+impl StatusOutput {
+    /// Get the current status label for the output, [None] when
+    /// the output is not active
+    async fn status(self, client: &obws::Client) -> Result<Option<String>, ObsError> {
+        Ok(match self {
+            StatusOutput::Recording => {
+                let status = client.recording().status().await?;
+                if !status.active {
+                    None
+                } else if status.paused {
+                    Some("PAUSED".to_string())
+                } else {
+                    Some(template::format_timecode(status.duration.whole_seconds()))
+                }
+            }
+            StatusOutput::Streaming => {
+                let status = client.streaming().status().await?;
+                if !status.active {
+                    None
+                } else if status.reconnecting {
+                    Some("RECONNECTING".to_string())
+                } else {
+                    Some(template::format_timecode(status.duration.whole_seconds()))
+                }
+            }
+        })
+    }
+
+    /// Whether the `event` is a state change for this output
+    fn is_state_event(self, event: &Event) -> bool {
+        matches!(
+            (self, event),
+            (StatusOutput::Recording, Event::RecordStateChanged { .. })
+                | (StatusOutput::Streaming, Event::StreamStateChanged { .. })
+        )
+    }
+}
+
+/// Shows the elapsed time of an output as the tile label, polling
+/// only happens while the output is active
+async fn output_status(
+    state: Rc<State>,
+    session: PluginSessionHandle,
+    tile_id: TileId,
+    label: TileLabel,
+    output: StatusOutput,
+    idle_label: String,
+) {
+    let mut events = state.subscribe_events();
+    let mut label = TileLabelWriter::new(session, tile_id, label);
+
+    loop {
+        let status = state
+            .clone()
+            .execute_with_client(async move |client| output.status(client).await)
+            .await;
+
+        match status {
+            Ok(Some(Some(text))) => {
+                label.set(text, None);
+                sleep(OUTPUT_STATUS_INTERVAL).await;
+            }
+
+            // Output is inactive or OBS is not connected
+            _ => {
+                label.set(idle_label.clone(), None);
+                wait_for_output_change(&mut events, output).await;
+            }
+        }
+    }
+}
+
+/// Shows the health of the stream output as the tile label, polling
+/// only happens while the stream is active
+async fn stream_stats(
+    state: Rc<State>,
+    session: PluginSessionHandle,
+    tile_id: TileId,
+    label: TileLabel,
+    thresholds: StreamStatsProperties,
+) {
+    let mut events = state.subscribe_events();
+    let mut label = TileLabelWriter::new(session, tile_id, label);
+
+    // No minimum bitrate unless one is specified
+    let bitrate_warning = thresholds.bitrate_warning.unwrap_or(0.0);
+    let dropped_frames_warning = thresholds
+        .dropped_frames_warning
+        .unwrap_or(DEFAULT_DROPPED_FRAMES_WARNING);
+    let congestion_warning = thresholds
+        .congestion_warning
+        .unwrap_or(DEFAULT_CONGESTION_WARNING);
+    let cpu_warning = thresholds.cpu_warning.unwrap_or(DEFAULT_CPU_WARNING);
+    let warning_color = thresholds
+        .warning_color
+        .unwrap_or_else(|| DEFAULT_WARNING_COLOR.to_string());
+
+    // Bytes sent by the output at the previous update
+    let mut last_bytes: Option<(u64, Instant)> = None;
+
+    loop {
+        let stats = state
+            .clone()
+            .execute_with_client(async move |client| {
+                let stream = client.streaming().status().await?;
+                if !stream.active {
+                    return Ok(None);
+                }
+
+                let stats = client.general().stats().await?;
+                Ok(Some((stream, stats)))
+            })
+            .await;
+
+        let (stream, stats) = match stats {
+            Ok(Some(Some(value))) => value,
+
+            // Stream is inactive or OBS is not connected
+            _ => {
+                last_bytes = None;
+                label.set("OFFLINE".to_string(), None);
+                wait_for_output_change(&mut events, StatusOutput::Streaming).await;
+                continue;
+            }
+        };
+
+        let now = Instant::now();
+        let bitrate = last_bytes.and_then(|(bytes, at)| {
+            let elapsed = now.duration_since(at).as_secs_f64();
+            if elapsed <= 0.0 {
+                return None;
+            }
+
+            let bits = stream.bytes.saturating_sub(bytes) as f64 * 8.0;
+            Some(bits / elapsed / 1000.0)
+        });
+        last_bytes = Some((stream.bytes, now));
+
+        let dropped = percentage(stream.skipped_frames, stream.total_frames);
+        let congestion = stream.congestion as f64 * 100.0;
+        let cpu = stats.cpu_usage;
+
+        let warning = bitrate.is_some_and(|bitrate| bitrate < bitrate_warning)
+            || dropped >= dropped_frames_warning
+            || congestion >= congestion_warning
+            || cpu >= cpu_warning;
+
+        let bitrate = match bitrate {
+            Some(value) => format!("{value:.0} kb/s"),
+            None => "-- kb/s".to_string(),
+        };
+
+        let text = format!("{bitrate}\n{dropped:.1}% drop\n{congestion:.0}% cong\nCPU {cpu:.0}%");
+        let color = warning.then(|| warning_color.clone());
+
+        label.set(text, color);
+        sleep(OUTPUT_STATUS_INTERVAL).await;
+    }
+}
+
+/// Shows whether a source filter is enabled as the tile label, updated
+/// whenever the enabled state of a filter changes
+async fn filter_status(
+    state: Rc<State>,
+    session: PluginSessionHandle,
+    tile_id: TileId,
+    label: TileLabel,
+    source: Uuid,
+    filter: String,
+    properties: ToggleFilterProperties,
+) {
+    let mut events = state.subscribe_events();
+    let mut label = TileLabelWriter::new(session, tile_id, label);
+
+    let enabled_label = properties
+        .enabled_label
+        .unwrap_or_else(|| DEFAULT_ENABLED_LABEL.to_string());
+    let disabled_label = properties
+        .disabled_label
+        .unwrap_or_else(|| DEFAULT_DISABLED_LABEL.to_string());
+
+    loop {
+        let filter_name = filter.clone();
+        let enabled = state
+            .clone()
+            .execute_with_client(async move |client| {
+                client
+                    .filters()
+                    .get(SourceId::Uuid(source), &filter_name)
+                    .await
+                    .map(|filter| filter.enabled)
+            })
+            .await;
+
+        if let Ok(Some(enabled)) = enabled {
+            let text = match enabled {
+                true => enabled_label.clone(),
+                false => disabled_label.clone(),
+            };
+
+            label.set(text, None);
+        }
+
+        // Events only include the source name so any change to a filter
+        // with a matching name triggers a refresh
+        loop {
+            match events.recv().await {
+                Ok(ClientEvent::Connected) => break,
+                Ok(ClientEvent::Obs(Event::SourceFilterEnableStateChanged {
+                    filter: changed,
+                    ..
+                })) if changed == filter => break,
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => break,
+                Err(RecvError::Closed) => std::future::pending().await,
+            }
+        }
+    }
+}
+
+/// Shows a single OBS performance metric as the tile label
+async fn performance_stats(
+    state: Rc<State>,
+    session: PluginSessionHandle,
+    tile_id: TileId,
+    label: TileLabel,
+    metric: PerformanceMetric,
+    interval: Duration,
+    properties: PerformanceStatsProperties,
+) {
+    let mut label = TileLabelWriter::new(session, tile_id, label);
+    let warning_color = properties
+        .warning_color
+        .unwrap_or_else(|| DEFAULT_WARNING_COLOR.to_string());
+
+    loop {
+        let stats = state
+            .clone()
+            .execute_with_client(async move |client| client.general().stats().await)
+            .await;
+
+        if let Ok(Some(stats)) = stats {
+            let value = metric_value(metric, &stats);
+            let warning = properties.warning_threshold.is_some_and(|threshold| {
+                if metric_warns_below(metric) {
+                    value < threshold
+                } else {
+                    value >= threshold
+                }
+            });
+
+            let text = format!("{}\n{}", metric_name(metric), format_metric(metric, value));
+            label.set(text, warning.then(|| warning_color.clone()));
+        }
+
+        sleep(interval).await;
+    }
+}
+
+fn metric_name(metric: PerformanceMetric) -> &'static str {
+    match metric {
+        PerformanceMetric::Fps => "FPS",
+        PerformanceMetric::RenderTime => "Render",
+        PerformanceMetric::RenderSkippedFrames => "Render Lag",
+        PerformanceMetric::OutputSkippedFrames => "Encode Lag",
+        PerformanceMetric::MemoryUsage => "Memory",
+        PerformanceMetric::DiskSpace => "Disk",
+    }
+}
+
+/// Get the value of the `metric` in the unit it is displayed in
+fn metric_value(metric: PerformanceMetric, stats: &Stats) -> f64 {
+    match metric {
+        PerformanceMetric::Fps => stats.active_fps,
+        PerformanceMetric::RenderTime => stats.average_frame_render_time,
+        PerformanceMetric::RenderSkippedFrames => {
+            percentage(stats.render_skipped_frames, stats.render_total_frames)
+        }
+        PerformanceMetric::OutputSkippedFrames => {
+            percentage(stats.output_skipped_frames, stats.output_total_frames)
+        }
+        PerformanceMetric::MemoryUsage => stats.memory_usage,
+        PerformanceMetric::DiskSpace => stats.available_disk_space / 1024.0,
+    }
+}
+
+fn format_metric(metric: PerformanceMetric, value: f64) -> String {
+    match metric {
+        PerformanceMetric::Fps => format!("{value:.1}"),
+        PerformanceMetric::RenderTime => format!("{value:.1} ms"),
+        PerformanceMetric::RenderSkippedFrames | PerformanceMetric::OutputSkippedFrames => {
+            format!("{value:.1}%")
+        }
+        PerformanceMetric::MemoryUsage => format!("{value:.0} MB"),
+        PerformanceMetric::DiskSpace => format!("{value:.1} GB"),
+    }
+}
+
+/// Whether the warning threshold for a metric is a lower bound
+fn metric_warns_below(metric: PerformanceMetric) -> bool {
+    matches!(
+        metric,
+        PerformanceMetric::Fps | PerformanceMetric::DiskSpace
+    )
+}
+
+/// Calculates the percentage of `value` out of `total`
+fn percentage(value: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+
+    value as f64 / total as f64 * 100.0
+}
+
+/// Writes labels to a tile, skipping updates that wouldn't change
+/// the current label
+struct TileLabelWriter {
+    session: PluginSessionHandle,
+    tile_id: TileId,
+    /// Label the tile had before any task changed it, used to keep
+    /// the users label styling
+    base: TileLabel,
+    /// Last written label text and colour
+    last: Option<(String, Option<String>)>,
+}
+
+impl TileLabelWriter {
+    fn new(session: PluginSessionHandle, tile_id: TileId, base: TileLabel) -> Self {
+        Self {
+            session,
+            tile_id,
+            base,
+            last: None,
+        }
+    }
+
+    /// Sets the label text, `color` overrides the label colour
+    fn set(&mut self, text: String, color: Option<String>) {
+        let next = (text, color);
+        if self.last.as_ref() == Some(&next) {
+            return;
+        }
+
+        let (text, color) = &next;
+        _ = self.session.set_tile_label(
+            self.tile_id,
+            TileLabel {
+                enabled: Some(true),
+                label: Some(text.clone()),
+                color: color.clone().or_else(|| self.base.color.clone()),
+                ..self.base.clone()
+            },
+        );
+
+        self.last = Some(next);
+    }
+}
+
+/// Waits until the state of the `output` changes or a new
+/// connection to OBS is established
+pub async fn wait_for_output_change(
+    events: &mut broadcast::Receiver<ClientEvent>,
+    output: StatusOutput,
+) {
+    loop {
+        match events.recv().await {
+            Ok(ClientEvent::Connected) => return,
+            Ok(ClientEvent::Obs(event)) if output.is_state_event(&event) => return,
+            Ok(_) => {}
+
+            // Missed events may have included a state change
+            Err(RecvError::Lagged(_)) => return,
+            Err(RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+/// Spaces out requests so that many tiles refreshing at the
+/// same time don't flood OBS with requests
+struct RateLimiter {
+    /// Next instant a request is allowed at
+    next: Cell<Instant>,
+    /// Minimum gap between requests
+    gap: Duration,
+}
+
+impl RateLimiter {
+    fn new(gap: Duration) -> Self {
+        Self {
+            next: Cell::new(Instant::now()),
+            gap,
+        }
+    }
+
+    /// Waits until the next request slot is available
+    async fn acquire(&self) {
+        let slot = self.next.get().max(Instant::now());
+        self.next.set(slot + self.gap);
+        sleep_until(slot).await;
+    }
+}
@@ -1,27 +1,113 @@
-use obws::requests::scenes::SceneId;
+use chrono::Local;
+use obws::{
+    requests::{
+        config::SetVideoSettings,
+        filters::{SetEnabled, SetSettings},
+        hotkeys::KeyModifiers,
+        inputs::{InputId, SetSettings as SetInputSettings},
+        profiles::SetParameter,
+        scene_items::{SetIndex, SetLocked, SetTransform},
+        scenes::SceneId,
+        sources::{SaveScreenshot, SourceId},
+    },
+    responses::StatusCode,
+};
 use serde::{Deserialize, Serialize};
-use std::rc::Rc;
+use std::{path::PathBuf, rc::Rc, time::Duration};
 use tilepad_plugin_sdk::{
-    Inspector, Plugin, PluginSessionHandle, TileInteractionContext,
+    DeviceId, DeviceIndicator, Inspector, Plugin, PluginSessionHandle, TileInteractionContext,
+    TileModel,
     tracing::{self},
 };
 use tokio::task::spawn_local;
+use uuid::Uuid;
 
 use crate::{
-    action::{Action, RecordingAction, StreamAction, VirtualCameraAction},
-    messages::{InspectorMessageIn, InspectorMessageOut, SelectOption},
-    state::{Auth, ClientState, State},
+    action::{
+        Action, CounterMode, CycleScenesMode, Easing, FilterAction, RawRequestMode,
+        RecordingAction, SceneItemLockAction, SceneItemOrderAction, ScreenshotTarget,
+        SendCaptionMode, StreamAction, TriggerHotkeyMode, VirtualCameraAction,
+    },
+    captions,
+    cycle::{SceneCycle, SceneCycles},
+    disk_guard::{self, DiskGuard},
+    indicator::{display_indicator, run_with_indicator},
+    messages::{InputOption, InspectorMessageIn, InspectorMessageOut, SelectOption},
+    monitor::TileMonitor,
+    raw::{self, RawRequest, RawResponse},
+    scene_items,
+    state::{Auth, ClientState, ObsError, State, StreamServicePreset},
+    template,
+    tile_tasks::TileTasks,
+    transform::{self, TransformAnimation, TransformPreset},
 };
 
+/// Default file name template for screenshots
+const DEFAULT_SCREENSHOT_FILE_NAME: &str = "Screenshot {date} {time}";
+
+/// Profile parameter category and name for the recording file name format
+const FILE_NAME_FORMAT_PARAMETER: (&str, &str) = ("Output", "FilenameFormatting");
+
+/// Property button on browser sources that refreshes the page without cache
+const BROWSER_REFRESH_BUTTON: &str = "refreshnocache";
+
+/// Button properties of the built in input kinds (Unversioned kind, button, label)
+///
+/// obs-websocket has no request for listing the properties of an input so
+/// only these buttons can be offered, buttons on other inputs (including
+/// third party sources) must be entered by name
+const INPUT_BUTTONS: &[(&str, &str, &str)] = &[
+    (
+        "browser_source",
+        BROWSER_REFRESH_BUTTON,
+        "Refresh cache of current page",
+    ),
+    ("dshow_input", "activate", "Activate / Deactivate"),
+    ("dshow_input", "video_config", "Configure Video"),
+    ("dshow_input", "xbar_config", "Configure Crossbar"),
+];
+
+/// Default duration of transform animations in milliseconds
+const DEFAULT_ANIMATION_DURATION: u64 = 500;
+
+/// Default and maximum transform updates per second while animating
+const DEFAULT_ANIMATION_FPS: u32 = 30;
+const MAX_ANIMATION_FPS: u32 = 60;
+
+/// Default seconds between scenes when auto advancing a scene cycle
+const DEFAULT_CYCLE_INTERVAL: u64 = 10;
+
+/// Default number of scenes that can be returned to with previous scene
+const DEFAULT_SCENE_HISTORY_DEPTH: usize = 10;
+
 /// Properties for the plugin itself
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Properties {
     pub auth: Option<Auth>,
+    /// Saved stream service configurations
+    #[serde(default)]
+    pub stream_services: Vec<StreamServicePreset>,
 }
 
 #[derive(Default)]
 pub struct ObsPlugin {
     state: Rc<State>,
+
+    /// Background tasks for the visible tiles
+    monitor: TileMonitor,
+
+    /// Disk space monitoring for recordings
+    disk_guard: DiskGuard,
+
+    /// Tasks started from tiles, such as caption feeds, transform animations
+    /// and auto advancing scene cycles
+    tile_tasks: TileTasks,
+
+    /// Scene cycle positions of tiles
+    scene_cycles: SceneCycles,
+
+    /// Saved stream service configurations from the plugin properties
+    stream_services: Vec<StreamServicePreset>,
 }
 
 impl ObsPlugin {
@@ -31,7 +117,33 @@ impl ObsPlugin {
 }
 
 impl Plugin for ObsPlugin {
+    fn on_registered(&mut self, session: &PluginSessionHandle) {
+        _ = session.request_visible_tiles();
+    }
+
+    fn on_device_tiles(
+        &mut self,
+        session: &PluginSessionHandle,
+        _device_id: DeviceId,
+        _tiles: Vec<TileModel>,
+    ) {
+        // Visible tiles across all devices are needed
+        _ = session.request_visible_tiles();
+    }
+
+    fn on_visible_tiles(&mut self, session: &PluginSessionHandle, tiles: Vec<TileModel>) {
+        self.disk_guard.update_tiles(&self.state, session, &tiles);
+        self.tile_tasks.update(session, &tiles);
+        self.monitor.update(&self.state, session, tiles);
+    }
+
     fn on_properties(&mut self, _session: &PluginSessionHandle, properties: serde_json::Value) {
+        let properties = serde_json::from_value::<Properties>(properties);
+
+        if let Ok(properties) = &properties {
+            self.stream_services = properties.stream_services.clone();
+        }
+
         // Nothing to do if already connected
         if matches!(
             self.state.get_state(),
@@ -40,7 +152,7 @@ impl Plugin for ObsPlugin {
             return;
         }
 
-        let properties = match serde_json::from_value::<Properties>(properties) {
+        let properties = match properties {
             Ok(value) => value,
 
             // Invalid properties
@@ -73,8 +185,11 @@ impl Plugin for ObsPlugin {
         self.state.set_inspector(Some(inspector));
     }
 
-    fn on_inspector_close(&mut self, _session: &PluginSessionHandle, _inspector: Inspector) {
+    fn on_inspector_close(&mut self, session: &PluginSessionHandle, _inspector: Inspector) {
         self.state.set_inspector(None);
+
+        // Tile properties may have changed
+        _ = session.request_visible_tiles();
     }
 
     fn on_inspector_message(
@@ -108,7 +223,7 @@ impl Plugin for ObsPlugin {
 
                 spawn_local(async move {
                     if state.try_connect(auth.clone(), false).await.is_ok() {
-                        _ = session.set_properties(Properties { auth: Some(auth) });
+                        _ = session.set_properties_partial(serde_json::json!({ "auth": auth }));
                     }
                 });
             }
@@ -160,6 +275,337 @@ impl Plugin for ObsPlugin {
                             .collect(),
                     });
 
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetSources => {
+                self.state.clone().run_with_client(async move |client| {
+                    let scenes = match client.scenes().list().await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get scenes");
+                            return Err(cause);
+                        }
+                    };
+
+                    let inputs = match client.inputs().list(None).await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get inputs");
+                            return Err(cause);
+                        }
+                    };
+
+                    // Scenes are sources too so they are included alongside inputs
+                    let scenes = scenes.scenes.into_iter().map(|scene| SelectOption {
+                        label: scene.id.name,
+                        value: scene.id.uuid.to_string(),
+                    });
+
+                    let inputs = inputs.into_iter().map(|input| SelectOption {
+                        label: input.id.name,
+                        value: input.id.uuid.to_string(),
+                    });
+
+                    _ = inspector.send(InspectorMessageOut::Sources {
+                        sources: scenes.chain(inputs).collect(),
+                    });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetRecordDirectory => {
+                self.state.clone().run_with_client(async move |client| {
+                    let directory = match client.config().record_directory().await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get record directory");
+                            return Err(cause);
+                        }
+                    };
+
+                    let (category, name) = FILE_NAME_FORMAT_PARAMETER;
+                    let file_name_format = match client.profiles().parameter(category, name).await {
+                        Ok(value) => value.value.or(value.default_value),
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get file name format");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::RecordDirectory {
+                        directory,
+                        file_name_format,
+                    });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetStreamServices => {
+                _ = inspector.send(InspectorMessageOut::StreamServices {
+                    presets: self.stream_services.iter().map(Into::into).collect(),
+                });
+            }
+            InspectorMessageIn::SaveStreamService { preset } => {
+                let name = preset.name.trim();
+                if name.is_empty() {
+                    return;
+                }
+
+                let existing = self
+                    .stream_services
+                    .iter()
+                    .position(|value| value.name == name);
+
+                // Keep the existing key when a new one isn't provided
+                let key = match preset.key.filter(|value| !value.is_empty()) {
+                    Some(value) => value,
+                    None => existing
+                        .map(|index| self.stream_services[index].key.clone())
+                        .unwrap_or_default(),
+                };
+
+                let preset = StreamServicePreset {
+                    name: name.to_string(),
+                    service_type: preset.service_type,
+                    service: preset.service.filter(|value| !value.trim().is_empty()),
+                    server: preset.server,
+                    key,
+                };
+
+                match existing {
+                    Some(index) => self.stream_services[index] = preset,
+                    None => self.stream_services.push(preset),
+                }
+
+                _ = session.set_properties_partial(serde_json::json!({
+                    "stream_services": self.stream_services
+                }));
+                _ = inspector.send(InspectorMessageOut::StreamServices {
+                    presets: self.stream_services.iter().map(Into::into).collect(),
+                });
+            }
+            InspectorMessageIn::DeleteStreamService { name } => {
+                self.stream_services.retain(|value| value.name != name);
+
+                _ = session.set_properties_partial(serde_json::json!({
+                    "stream_services": self.stream_services
+                }));
+                _ = inspector.send(InspectorMessageOut::StreamServices {
+                    presets: self.stream_services.iter().map(Into::into).collect(),
+                });
+            }
+            InspectorMessageIn::GetVideoSettings => {
+                self.state.clone().run_with_client(async move |client| {
+                    let settings = match client.config().video_settings().await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get video settings");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::VideoSettings {
+                        base_width: settings.base_width,
+                        base_height: settings.base_height,
+                        output_width: settings.output_width,
+                        output_height: settings.output_height,
+                        fps_numerator: settings.fps_numerator,
+                        fps_denominator: settings.fps_denominator,
+                    });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetSourceFilters { source } => {
+                let source_id = match Uuid::parse_str(&source) {
+                    Ok(value) => value,
+                    Err(_) => return,
+                };
+
+                self.state.clone().run_with_client(async move |client| {
+                    let filters = match client.filters().list(SourceId::Uuid(source_id)).await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get source filters");
+                            return Err(cause);
+                        }
+                    };
+
+                    // Filters are identified by their name within a source
+                    _ = inspector.send(InspectorMessageOut::SourceFilters {
+                        source,
+                        filters: filters
+                            .into_iter()
+                            .map(|filter| SelectOption {
+                                label: filter.name.clone(),
+                                value: filter.name,
+                            })
+                            .collect(),
+                    });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetFilterSettings { source, filter } => {
+                let source_id = match Uuid::parse_str(&source) {
+                    Ok(value) => value,
+                    Err(_) => return,
+                };
+
+                self.state.clone().run_with_client(async move |client| {
+                    let settings = match client
+                        .filters()
+                        .get(SourceId::Uuid(source_id), &filter)
+                        .await
+                    {
+                        Ok(value) => value.settings,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get source filter");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::FilterSettings {
+                        source,
+                        filter,
+                        settings,
+                    });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetInputs { kinds } => {
+                self.state.clone().run_with_client(async move |client| {
+                    let inputs = match client.inputs().list(None).await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get inputs");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::Inputs {
+                        inputs: inputs
+                            .into_iter()
+                            .filter(|input| {
+                                kinds.is_empty() || kinds.contains(&input.unversioned_kind)
+                            })
+                            .map(|input| InputOption {
+                                label: input.id.name,
+                                value: input.id.uuid.to_string(),
+                                kind: input.unversioned_kind,
+                            })
+                            .collect(),
+                    });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetInputSettings { input } => {
+                let input_id = match Uuid::parse_str(&input) {
+                    Ok(value) => value,
+                    Err(_) => return,
+                };
+
+                self.state.clone().run_with_client(async move |client| {
+                    let settings = match client
+                        .inputs()
+                        .settings::<serde_json::Value>(InputId::Uuid(input_id))
+                        .await
+                    {
+                        Ok(value) => value.settings,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get input settings");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::InputSettings { input, settings });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetInputButtons { input, kind } => {
+                let buttons = INPUT_BUTTONS
+                    .iter()
+                    .filter(|(button_kind, _, _)| *button_kind == kind)
+                    .map(|(_, button, label)| SelectOption {
+                        label: label.to_string(),
+                        value: button.to_string(),
+                    })
+                    .collect();
+
+                _ = inspector.send(InspectorMessageOut::InputButtons { input, buttons });
+            }
+            InspectorMessageIn::GetSceneItems { scene } => {
+                let scene_id = match Uuid::parse_str(&scene) {
+                    Ok(value) => value,
+                    Err(_) => return,
+                };
+
+                self.state.clone().run_with_client(async move |client| {
+                    let items = match scene_items::list_scene_item_tree(client, scene_id).await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get scene items");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::SceneItems { scene, items });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetSceneItemTransform { scene, item } => {
+                let scene_id = match Uuid::parse_str(&scene) {
+                    Ok(value) => value,
+                    Err(_) => return,
+                };
+
+                self.state.clone().run_with_client(async move |client| {
+                    let transform = match client
+                        .scene_items()
+                        .transform(SceneId::Uuid(scene_id), item)
+                        .await
+                    {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get scene item transform");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::SceneItemTransform {
+                        scene,
+                        item,
+                        transform: TransformPreset::from_current(&transform),
+                    });
+
+                    Ok(())
+                });
+            }
+            InspectorMessageIn::GetHotkeys => {
+                self.state.clone().run_with_client(async move |client| {
+                    let hotkeys = match client.hotkeys().list().await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get hotkeys");
+                            return Err(cause);
+                        }
+                    };
+
+                    _ = inspector.send(InspectorMessageOut::Hotkeys {
+                        hotkeys: hotkeys
+                            .into_iter()
+                            .map(|hotkey| SelectOption {
+                                label: hotkey.clone(),
+                                value: hotkey,
+                            })
+                            .collect(),
+                    });
+
                     Ok(())
                 });
             }
@@ -168,11 +614,13 @@ impl Plugin for ObsPlugin {
 
     fn on_tile_clicked(
         &mut self,
-        _session: &PluginSessionHandle,
+        session: &PluginSessionHandle,
         ctx: TileInteractionContext,
         properties: serde_json::Value,
     ) {
         let action_id = ctx.action_id.as_str();
+        // Tasks started from the tile are stopped when its properties change
+        let tile_properties = properties.clone();
         let action = match Action::from_action(action_id, properties) {
             Some(Ok(value)) => value,
             Some(Err(cause)) => {
@@ -192,15 +640,54 @@ impl Plugin for ObsPlugin {
                     None => return,
                 };
 
+                let disk_guard = properties.disk_guard;
+                self.disk_guard.update_pressed(
+                    &self.state,
+                    session,
+                    ctx.clone(),
+                    disk_guard.critical_disk_space,
+                );
+
+                let session = session.clone();
+
                 self.state.clone().run_with_client(async move |client| {
                     match action {
                         RecordingAction::StartStop => {
+                            // Check disk space if the toggle will start recording
+                            if disk_guard.min_disk_space.is_some() {
+                                let status = match client.recording().status().await {
+                                    Ok(value) => value,
+                                    Err(cause) => {
+                                        tracing::error!(?cause, "failed to get recording status");
+                                        return Err(cause);
+                                    }
+                                };
+
+                                if !status.active
+                                    && !disk_guard::check_before_start(
+                                        client,
+                                        &session,
+                                        &ctx,
+                                        &disk_guard,
+                                    )
+                                    .await?
+                                {
+                                    return Ok(());
+                                }
+                            }
+
                             if let Err(cause) = client.recording().toggle().await {
                                 tracing::error!(?cause, "failed to toggle recording");
                                 return Err(cause);
                             }
                         }
                         RecordingAction::Start => {
+                            if !disk_guard::check_before_start(client, &session, &ctx, &disk_guard)
+                                .await?
+                            {
+                                return Ok(());
+                            }
+
                             if let Err(cause) = client.recording().start().await {
                                 tracing::error!(?cause, "failed to start recording");
                                 return Err(cause);
@@ -230,6 +717,44 @@ impl Plugin for ObsPlugin {
                                 return Err(cause);
                             }
                         }
+                        RecordingAction::Split => {
+                            if let Err(cause) = client.recording().split_file().await {
+                                if is_unsupported_request(&cause) {
+                                    tracing::error!(
+                                        "splitting recordings requires OBS 30.2 (obs-websocket 5.5) or newer"
+                                    );
+                                } else {
+                                    tracing::error!(?cause, "failed to split recording");
+                                }
+
+                                display_indicator(&session, &ctx, DeviceIndicator::Error);
+                                return Err(cause);
+                            }
+                        }
+                        RecordingAction::AddChapter { name } => {
+                            let name = match name.filter(|name| !name.trim().is_empty()) {
+                                Some(name) => {
+                                    Some(template::render_with_obs(client, &name, |_| None).await?)
+                                }
+                                None => None,
+                            };
+
+                            if let Err(cause) =
+                                client.recording().create_chapter(name.as_deref()).await
+                            {
+                                if is_unsupported_request(&cause) {
+                                    tracing::error!(
+                                        "recording chapters require OBS 30.2 (obs-websocket 5.5) or newer"
+                                    );
+                                } else {
+                                    // Chapters are only supported by some formats (Hybrid MP4)
+                                    tracing::error!(?cause, "failed to create recording chapter");
+                                }
+
+                                display_indicator(&session, &ctx, DeviceIndicator::Error);
+                                return Err(cause);
+                            }
+                        }
                     }
 
                     Ok(())
@@ -335,6 +860,912 @@ impl Plugin for ObsPlugin {
                     Ok(())
                 });
             }
-        }
+            Action::TriggerHotkey(properties) => {
+                match properties.mode.unwrap_or(TriggerHotkeyMode::Name) {
+                    TriggerHotkeyMode::Name => {
+                        let hotkey = match properties.hotkey {
+                            Some(value) => value,
+                            None => return,
+                        };
+
+                        run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                            let hotkeys = client.hotkeys();
+                            if let Err(cause) = hotkeys.trigger_by_name(&hotkey, None).await {
+                                tracing::error!(?cause, "failed to trigger hotkey");
+                                return Err(cause);
+                            }
+
+                            Ok(true)
+                        });
+                    }
+                    TriggerHotkeyMode::KeySequence => {
+                        let key_id = match properties.key_id {
+                            Some(value) if !value.trim().is_empty() => value,
+                            _ => return,
+                        };
+
+                        let modifiers = properties.modifiers;
+                        let modifiers = KeyModifiers {
+                            shift: modifiers.shift,
+                            control: modifiers.control,
+                            alt: modifiers.alt,
+                            command: modifiers.command,
+                        };
+
+                        run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                            let hotkeys = client.hotkeys();
+                            if let Err(cause) =
+                                hotkeys.trigger_by_sequence(&key_id, modifiers).await
+                            {
+                                tracing::error!(?cause, "failed to trigger hotkey sequence");
+                                return Err(cause);
+                            }
+
+                            Ok(true)
+                        });
+                    }
+                }
+            }
+            Action::Screenshot(properties) => {
+                let directory = match properties.directory {
+                    Some(value) if !value.trim().is_empty() => PathBuf::from(value),
+                    _ => return,
+                };
+
+                let source_id = match properties.target.unwrap_or(ScreenshotTarget::Program) {
+                    ScreenshotTarget::Program => None,
+                    ScreenshotTarget::Source => {
+                        match properties.source.as_deref().map(Uuid::parse_str) {
+                            Some(Ok(value)) => Some(value),
+                            _ => return,
+                        }
+                    }
+                };
+
+                let file_name = properties
+                    .file_name
+                    .filter(|value| !value.trim().is_empty())
+                    .unwrap_or_else(|| DEFAULT_SCREENSHOT_FILE_NAME.to_string());
+                let format = properties.format.unwrap_or_else(|| "png".to_string());
+                let width = properties.width;
+                let height = properties.height;
+                let quality = properties.quality;
+
+                let tile_id = ctx.tile_id;
+                let tile_session = session.clone();
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    let scene = match client.scenes().current_program_scene().await {
+                        Ok(value) => value.id,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get current scene");
+                            return Err(cause);
+                        }
+                    };
+
+                    // Program screenshots capture the current program scene
+                    let source_id = source_id.unwrap_or(scene.uuid);
+
+                    let now = Local::now();
+                    let file_name = template::render(&file_name, |name, format| match name {
+                        "scene" => Some(template::sanitize_file_name(&scene.name)),
+                        _ => template::resolve_date_time(&now, name, format),
+                    });
+                    let file_path = directory.join(format!("{file_name}.{format}"));
+
+                    let sources = client.sources();
+                    if let Err(cause) = sources
+                        .save_screenshot(SaveScreenshot {
+                            source: SourceId::Uuid(source_id),
+                            format: &format,
+                            width,
+                            height,
+                            compression_quality: quality,
+                            file_path: &file_path,
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to save screenshot");
+                        return Err(cause);
+                    }
+
+                    // Report the saved file back to the tile
+                    _ = tile_session.set_tile_properties_partial(
+                        tile_id,
+                        serde_json::json!({ "last_path": file_path }),
+                    );
+
+                    Ok(true)
+                });
+            }
+            Action::SendCaption(properties) => {
+                match properties.mode.unwrap_or(SendCaptionMode::Text) {
+                    SendCaptionMode::Text => {
+                        let text = match properties.text {
+                            Some(value) if !value.trim().is_empty() => value,
+                            _ => return,
+                        };
+
+                        run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                            captions::send_caption(client, &text).await?;
+                            Ok(true)
+                        });
+                    }
+                    SendCaptionMode::Feed => {
+                        let feed_path = match properties.feed_path {
+                            Some(value) if !value.trim().is_empty() => PathBuf::from(value),
+                            _ => return,
+                        };
+
+                        captions::toggle_caption_feed(
+                            &self.tile_tasks,
+                            self.state.clone(),
+                            session.clone(),
+                            ctx,
+                            tile_properties,
+                            feed_path,
+                        );
+                    }
+                }
+            }
+            Action::SetRecordDirectory(properties) => {
+                let directory = match properties.directory {
+                    Some(value) if !value.trim().is_empty() => value,
+                    _ => return,
+                };
+
+                let project = properties.project.unwrap_or_default();
+                let file_name_format = properties
+                    .file_name_format
+                    .filter(|value| !value.trim().is_empty());
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    let status = match client.recording().status().await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get recording status");
+                            return Err(cause);
+                        }
+                    };
+
+                    // Changing the output while recording would split the recording
+                    // across directories
+                    if status.active {
+                        tracing::warn!("cannot change record directory while recording");
+                        return Ok(false);
+                    }
+
+                    let profile = match directory.contains("{profile") {
+                        true => match client.profiles().current().await {
+                            Ok(value) => Some(value),
+                            Err(cause) => {
+                                tracing::error!(?cause, "failed to get current profile");
+                                return Err(cause);
+                            }
+                        },
+                        false => None,
+                    };
+
+                    let now = Local::now();
+                    let directory = template::render(&directory, |name, format| match name {
+                        "project" => Some(template::sanitize_file_name(&project)),
+                        "profile" => profile.as_deref().map(template::sanitize_file_name),
+                        _ => template::resolve_date_time(&now, name, format),
+                    });
+
+                    if let Err(cause) = client.config().set_record_directory(&directory).await {
+                        if is_unsupported_request(&cause) {
+                            tracing::error!(
+                                "setting the record directory requires obs-websocket 5.3 or newer"
+                            );
+                        } else {
+                            tracing::error!(?cause, "failed to set record directory");
+                        }
+
+                        return Err(cause);
+                    }
+
+                    if let Some(file_name_format) = file_name_format {
+                        let (category, name) = FILE_NAME_FORMAT_PARAMETER;
+                        if let Err(cause) = client
+                            .profiles()
+                            .set_parameter(SetParameter {
+                                category,
+                                name,
+                                value: Some(&file_name_format),
+                            })
+                            .await
+                        {
+                            tracing::error!(?cause, "failed to set file name format");
+                            return Err(cause);
+                        }
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::SetStreamService(properties) => {
+                let preset = match properties.preset.and_then(|name| {
+                    self.stream_services
+                        .iter()
+                        .find(|value| value.name == name)
+                        .cloned()
+                }) {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    let mut settings = serde_json::json!({
+                        "server": preset.server,
+                        "key": preset.key,
+                    });
+
+                    // Only services from the rtmp_common list have a name
+                    if let Some(service) = preset.service {
+                        settings["service"] = serde_json::Value::String(service);
+                    }
+
+                    // OBS refuses to change the service while streaming
+                    if let Err(cause) = client
+                        .config()
+                        .set_stream_service_settings(&preset.service_type, &settings)
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to set stream service settings");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::SetVideoSettings(properties) => {
+                // Each pair of values must be provided together
+                let (base_width, base_height) = both(properties.base_width, properties.base_height);
+                let (output_width, output_height) =
+                    both(properties.output_width, properties.output_height);
+                let (fps_numerator, fps_denominator) =
+                    both(properties.fps_numerator, properties.fps_denominator);
+
+                let settings = SetVideoSettings {
+                    fps_numerator,
+                    fps_denominator,
+                    base_width,
+                    base_height,
+                    output_width,
+                    output_height,
+                };
+
+                if base_width.is_none() && output_width.is_none() && fps_numerator.is_none() {
+                    return;
+                }
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    let outputs = match client.outputs().list().await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get outputs");
+                            return Err(cause);
+                        }
+                    };
+
+                    // Changing video settings while an output is active would
+                    // interrupt the output
+                    let active: Vec<&str> = outputs
+                        .iter()
+                        .filter(|output| output.active)
+                        .map(|output| output.name.as_str())
+                        .collect();
+
+                    if !active.is_empty() {
+                        tracing::warn!(
+                            ?active,
+                            "cannot change video settings while outputs are active"
+                        );
+                        return Ok(false);
+                    }
+
+                    if let Err(cause) = client.config().set_video_settings(settings).await {
+                        tracing::error!(?cause, "failed to set video settings");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::ToggleFilter(properties) => {
+                let source_id = match properties.source.as_deref().map(Uuid::parse_str) {
+                    Some(Ok(value)) => value,
+                    _ => return,
+                };
+
+                let filter = match properties.filter {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                let action = properties.action.unwrap_or(FilterAction::Toggle);
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    let filters = client.filters();
+                    let source = SourceId::Uuid(source_id);
+
+                    let enabled = match action {
+                        FilterAction::Enable => true,
+                        FilterAction::Disable => false,
+                        FilterAction::Toggle => match filters.get(source, &filter).await {
+                            Ok(value) => !value.enabled,
+                            Err(cause) => {
+                                tracing::error!(?cause, "failed to get source filter");
+                                return Err(cause);
+                            }
+                        },
+                    };
+
+                    if let Err(cause) = filters
+                        .set_enabled(SetEnabled {
+                            source,
+                            filter: &filter,
+                            enabled,
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to set filter enabled");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::SetFilterSettings(properties) => {
+                let source_id = match properties.source.as_deref().map(Uuid::parse_str) {
+                    Some(Ok(value)) => value,
+                    _ => return,
+                };
+
+                let filter = match properties.filter {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                let settings = match parse_settings_overlay(properties.settings.as_deref()) {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    if let Err(cause) = client
+                        .filters()
+                        .set_settings(SetSettings {
+                            source: SourceId::Uuid(source_id),
+                            filter: &filter,
+                            settings: &settings,
+                            overlay: Some(true),
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to set filter settings");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::SetInputSettings(properties) => {
+                let input_id = match properties.input.as_deref().map(Uuid::parse_str) {
+                    Some(Ok(value)) => value,
+                    _ => return,
+                };
+
+                let settings = match parse_settings_overlay(properties.settings.as_deref()) {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    if let Err(cause) = client
+                        .inputs()
+                        .set_settings(SetInputSettings {
+                            input: InputId::Uuid(input_id),
+                            settings: &settings,
+                            overlay: Some(true),
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to set input settings");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::UpdateText(properties) => {
+                let input_id = match properties.input.as_deref().map(Uuid::parse_str) {
+                    Some(Ok(value)) => value,
+                    _ => return,
+                };
+
+                let text_template = match properties.template {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                let step = properties.counter_step.unwrap_or(1);
+                let start = properties.counter_start.unwrap_or(0);
+                let counter = match properties.counter_mode.unwrap_or(CounterMode::Update) {
+                    CounterMode::Update => properties.counter,
+                    CounterMode::Increment => properties.counter.saturating_add(step),
+                    CounterMode::Decrement => properties.counter.saturating_sub(step),
+                    CounterMode::Reset => start,
+                };
+
+                let changed = counter != properties.counter;
+                let tile_id = ctx.tile_id;
+                let tile_session = session.clone();
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    let text = template::render_with_obs(client, &text_template, |variable| {
+                        (variable == "counter").then(|| counter.to_string())
+                    })
+                    .await?;
+                    let settings = serde_json::json!({ "text": text });
+
+                    if let Err(cause) = client
+                        .inputs()
+                        .set_settings(SetInputSettings {
+                            input: InputId::Uuid(input_id),
+                            settings: &settings,
+                            overlay: Some(true),
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to update text");
+                        return Err(cause);
+                    }
+
+                    // Persist the counter for the next press once the text is shown
+                    if changed {
+                        _ = tile_session.set_tile_properties_partial(
+                            tile_id,
+                            serde_json::json!({ "counter": counter }),
+                        );
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::RefreshBrowserSource(properties) => {
+                let input_id = match properties.input.as_deref().map(Uuid::parse_str) {
+                    Some(Ok(value)) => value,
+                    _ => return,
+                };
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    if let Err(cause) = client
+                        .inputs()
+                        .press_properties_button(InputId::Uuid(input_id), BROWSER_REFRESH_BUTTON)
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to refresh browser source");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::PressInputButton(properties) => {
+                let input_id = match properties.input.as_deref().map(Uuid::parse_str) {
+                    Some(Ok(value)) => value,
+                    _ => return,
+                };
+
+                let button = match properties.button {
+                    Some(value) if !value.trim().is_empty() => value,
+                    _ => return,
+                };
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    if let Err(cause) = client
+                        .inputs()
+                        .press_properties_button(InputId::Uuid(input_id), &button)
+                        .await
+                    {
+                        tracing::error!(?cause, ?button, "failed to press input button");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::SetSceneItemTransform(properties) => {
+                let (scene_id, item_id) = match properties.target.resolve() {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                let transform = properties.transform;
+                if transform.is_empty() {
+                    return;
+                }
+
+                if properties.animate {
+                    let fps = properties
+                        .animation_fps
+                        .unwrap_or(DEFAULT_ANIMATION_FPS)
+                        .clamp(1, MAX_ANIMATION_FPS);
+                    let duration = properties
+                        .animation_duration
+                        .unwrap_or(DEFAULT_ANIMATION_DURATION);
+
+                    transform::toggle_transform_animation(
+                        &self.tile_tasks,
+                        self.state.clone(),
+                        ctx.tile_id,
+                        tile_properties,
+                        TransformAnimation {
+                            scene: scene_id,
+                            item_id,
+                            target: transform,
+                            duration: Duration::from_millis(duration),
+                            easing: properties.animation_easing.unwrap_or(Easing::EaseInOut),
+                            frame_interval: Duration::from_secs(1) / fps,
+                        },
+                    );
+                    return;
+                }
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    if let Err(cause) = client
+                        .scene_items()
+                        .set_transform(SetTransform {
+                            scene: SceneId::Uuid(scene_id),
+                            item_id,
+                            transform: transform.to_request(),
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to set scene item transform");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::SceneItemOrder(properties) => {
+                let (scene_id, item_id) = match properties.target.resolve() {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                let group = properties.target.in_group();
+
+                let action = match properties.action {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    // Groups must be listed with GetGroupSceneItemList
+                    let items = match scene_items::list_items(client, scene_id, group).await {
+                        Ok(value) => value,
+                        Err(cause) => {
+                            tracing::error!(?cause, "failed to get scene items");
+                            return Err(cause);
+                        }
+                    };
+
+                    let scene_items = client.scene_items();
+                    let scene = SceneId::Uuid(scene_id);
+
+                    let index = match items.iter().find(|item| item.id == item_id) {
+                        Some(value) => value.index,
+                        // Item was removed from the scene
+                        None => return Ok(false),
+                    };
+
+                    // Index 0 is the bottom of the scene
+                    let top = items.len().saturating_sub(1) as u32;
+                    let new_index = match action {
+                        SceneItemOrderAction::Up => index.saturating_add(1).min(top),
+                        SceneItemOrderAction::Down => index.saturating_sub(1),
+                        SceneItemOrderAction::Top => top,
+                        SceneItemOrderAction::Bottom => 0,
+                    };
+
+                    if new_index == index {
+                        return Ok(true);
+                    }
+
+                    if let Err(cause) = scene_items
+                        .set_index(SetIndex {
+                            scene,
+                            item_id,
+                            index: new_index,
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to set scene item index");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::SceneItemLock(properties) => {
+                let (scene_id, item_id) = match properties.target.resolve() {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                let action = properties.action.unwrap_or(SceneItemLockAction::Toggle);
+
+                run_with_indicator(self.state.clone(), session, ctx, async move |client| {
+                    let scene_items = client.scene_items();
+                    let scene = SceneId::Uuid(scene_id);
+
+                    let locked = match action {
+                        SceneItemLockAction::Lock => true,
+                        SceneItemLockAction::Unlock => false,
+                        SceneItemLockAction::Toggle => {
+                            match scene_items.locked(scene, item_id).await {
+                                Ok(value) => !value,
+                                Err(cause) => {
+                                    tracing::error!(?cause, "failed to get scene item locked");
+                                    return Err(cause);
+                                }
+                            }
+                        }
+                    };
+
+                    if let Err(cause) = scene_items
+                        .set_locked(SetLocked {
+                            scene,
+                            item_id,
+                            locked,
+                        })
+                        .await
+                    {
+                        tracing::error!(?cause, "failed to set scene item locked");
+                        return Err(cause);
+                    }
+
+                    Ok(true)
+                });
+            }
+            Action::CycleScenes(properties) => {
+                // Invalid scenes are skipped rather than breaking the cycle
+                let scenes: Vec<Uuid> = properties
+                    .scenes
+                    .iter()
+                    .filter_map(|scene| Uuid::parse_str(scene).ok())
+                    .collect();
+
+                if scenes.is_empty() {
+                    return;
+                }
+
+                let cycle = SceneCycle {
+                    scenes,
+                    mode: properties.mode.unwrap_or(CycleScenesMode::Wrap),
+                };
+
+                if properties.auto_advance {
+                    let interval = properties.interval.unwrap_or(DEFAULT_CYCLE_INTERVAL).max(1);
+
+                    self.scene_cycles.toggle_auto_advance(
+                        &self.tile_tasks,
+                        self.state.clone(),
+                        ctx.tile_id,
+                        tile_properties,
+                        cycle,
+                        Duration::from_secs(interval),
+                    );
+                } else {
+                    self.scene_cycles
+                        .advance(self.state.clone(), ctx.tile_id, cycle);
+                }
+            }
+            Action::PreviousScene(properties) => {
+                let depth = properties
+                    .depth
+                    .unwrap_or(DEFAULT_SCENE_HISTORY_DEPTH)
+                    .max(1);
+
+                let scene = match self.state.start_previous_scene(depth) {
+                    Some(value) => value,
+                    None => return,
+                };
+
+                let state = self.state.clone();
+                spawn_local(async move {
+                    let result = state
+                        .clone()
+                        .execute_with_client(async move |client| {
+                            let scenes = client.scenes();
+
+                            if let Err(cause) =
+                                scenes.set_current_program_scene(SceneId::Uuid(scene)).await
+                            {
+                                tracing::error!(?cause, "failed to set current scene");
+                                return Err(cause);
+                            }
+
+                            Ok(())
+                        })
+                        .await;
+
+                    // History is kept when the scene couldn't be switched to
+                    if !matches!(result, Ok(Some(_))) {
+                        state.cancel_previous_scene(scene);
+                    }
+                });
+            }
+            // Display only actions, updated by the tile monitor
+            Action::StreamStats(_) | Action::PerformanceStats(_) => {}
+            Action::RawRequest(properties) => {
+                // Raw requests use their own connection with the current authentication
+                let auth = match self.state.get_auth() {
+                    Some(value) => value,
+                    None => {
+                        display_indicator(session, &ctx, DeviceIndicator::Error);
+                        return;
+                    }
+                };
+
+                match properties.mode.unwrap_or(RawRequestMode::Single) {
+                    RawRequestMode::Single => {
+                        let request_type = match properties.request_type {
+                            Some(value) if !value.trim().is_empty() => value,
+                            _ => return,
+                        };
+
+                        let request_data = match properties.request_data.as_deref() {
+                            Some(value) if !value.trim().is_empty() => {
+                                match serde_json::from_str(value) {
+                                    Ok(value) => Some(value),
+                                    Err(cause) => {
+                                        tracing::error!(?cause, "invalid raw request data");
+                                        display_indicator(session, &ctx, DeviceIndicator::Error);
+                                        return;
+                                    }
+                                }
+                            }
+                            _ => None,
+                        };
+
+                        let request = RawRequest {
+                            request_type,
+                            request_data,
+                        };
+
+                        let session = session.clone();
+
+                        spawn_local(async move {
+                            let succeeded = match raw::send_request(&auth, request).await {
+                                Ok(response) => {
+                                    log_raw_response(&response);
+                                    response.request_status.result
+                                }
+                                Err(cause) => {
+                                    tracing::error!(%cause, "failed to send raw request");
+                                    false
+                                }
+                            };
+
+                            display_raw_indicator(&session, &ctx, succeeded);
+                        });
+                    }
+                    RawRequestMode::Batch => {
+                        let requests: Vec<RawRequest> = match properties.batch.as_deref() {
+                            Some(value) => match serde_json::from_str(value) {
+                                Ok(value) => value,
+                                Err(cause) => {
+                                    tracing::error!(?cause, "invalid raw request batch");
+                                    display_indicator(session, &ctx, DeviceIndicator::Error);
+                                    return;
+                                }
+                            },
+                            None => return,
+                        };
+
+                        if requests.is_empty() {
+                            return;
+                        }
+
+                        let halt_on_failure = properties.halt_on_failure;
+
+                        let session = session.clone();
+
+                        spawn_local(async move {
+                            let succeeded =
+                                match raw::send_batch(&auth, requests, halt_on_failure).await {
+                                    Ok(responses) => {
+                                        responses.iter().for_each(log_raw_response);
+                                        responses
+                                            .iter()
+                                            .all(|response| response.request_status.result)
+                                    }
+                                    Err(cause) => {
+                                        tracing::error!(%cause, "failed to send raw request batch");
+                                        false
+                                    }
+                                };
+
+                            display_raw_indicator(&session, &ctx, succeeded);
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses a JSON object of settings to overlay onto existing settings
+fn parse_settings_overlay(value: Option<&str>) -> Option<serde_json::Value> {
+    let value = value.filter(|value| !value.trim().is_empty())?;
+
+    match serde_json::from_str::<serde_json::Value>(value) {
+        Ok(value) if value.is_object() => Some(value),
+        Ok(_) => {
+            tracing::error!("settings overlay must be a JSON object");
+            None
+        }
+        Err(cause) => {
+            tracing::error!(?cause, "invalid settings overlay");
+            None
+        }
+    }
+}
+
+/// Only provides the pair of values when both are present
+fn both<T>(a: Option<T>, b: Option<T>) -> (Option<T>, Option<T>) {
+    match (a, b) {
+        (Some(a), Some(b)) => (Some(a), Some(b)),
+        _ => (None, None),
+    }
+}
+
+/// Whether the request failed because the connected version of
+/// obs-websocket does not support the request
+fn is_unsupported_request(cause: &ObsError) -> bool {
+    matches!(
+        cause,
+        ObsError::Api {
+            code: StatusCode::UnknownRequestType,
+            ..
+        }
+    )
+}
+
+/// Shows whether every raw request sent from the tile succeeded
+fn display_raw_indicator(
+    session: &PluginSessionHandle,
+    ctx: &TileInteractionContext,
+    succeeded: bool,
+) {
+    let indicator = match succeeded {
+        true => DeviceIndicator::Success,
+        false => DeviceIndicator::Error,
+    };
+
+    display_indicator(session, ctx, indicator);
+}
+
+/// Logs the outcome of a raw request
+fn log_raw_response(response: &RawResponse) {
+    let status = &response.request_status;
+    if status.result {
+        tracing::debug!(
+            request_type = ?response.request_type,
+            response_data = ?response.response_data,
+            "raw request completed"
+        );
+    } else {
+        tracing::error!(
+            request_type = ?response.request_type,
+            code = status.code,
+            comment = ?status.comment,
+            "raw request failed"
+        );
     }
 }
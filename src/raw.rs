@@ -0,0 +1,239 @@
+//! Minimal obs-websocket client for sending untyped requests
+//!
+//! obws only exposes typed requests, raw requests are sent over their
+//! own short lived connection using the current authentication details
+
+use std::time::Duration;
+
+use base64::{Engine, engine::general_purpose};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::time::timeout;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async, tungstenite::Message};
+
+use crate::state::Auth;
+
+type Socket = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Request ID used for requests sent over the raw connection, only
+/// a single request is ever in flight so this does not need to be unique
+const REQUEST_ID: &str = "tilepad";
+
+/// Maximum time to wait for a message from OBS
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum RawError {
+    /// Error from the underlying websocket
+    WebSocket(tokio_tungstenite::tungstenite::Error),
+    /// Failed to serialize or deserialize a message
+    Serde(serde_json::Error),
+    /// Connection closed or timed out before a response was received
+    Closed,
+    /// Server did not complete the identification handshake
+    Handshake,
+}
+
+impl std::fmt::Display for RawError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WebSocket(err) => write!(f, "websocket error: {err}"),
+            Self::Serde(err) => write!(f, "invalid message: {err}"),
+            Self::Closed => {
+                f.write_str("connection closed or timed out before a response was received")
+            }
+            Self::Handshake => f.write_str("server did not complete the handshake"),
+        }
+    }
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for RawError {
+    fn from(value: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocket(value)
+    }
+}
+
+impl From<serde_json::Error> for RawError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Serde(value)
+    }
+}
+
+/// Request to send to OBS
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawRequest {
+    pub request_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_data: Option<serde_json::Value>,
+}
+
+/// Response to a request sent to OBS
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawResponse {
+    pub request_type: String,
+    pub request_status: RawStatus,
+    #[serde(default)]
+    pub response_data: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawStatus {
+    pub result: bool,
+    pub code: u16,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+/// Message received from the server
+#[derive(Deserialize)]
+struct ServerMessage {
+    op: u8,
+    d: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Hello {
+    rpc_version: u32,
+    authentication: Option<Authentication>,
+}
+
+#[derive(Deserialize)]
+struct Authentication {
+    challenge: String,
+    salt: String,
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    results: Vec<RawResponse>,
+}
+
+/// Sends a single request to OBS
+pub async fn send_request(auth: &Auth, request: RawRequest) -> Result<RawResponse, RawError> {
+    let mut socket = connect(auth).await?;
+
+    let mut message = json!({
+        "requestType": request.request_type,
+        "requestId": REQUEST_ID,
+    });
+
+    if let Some(request_data) = request.request_data {
+        message["requestData"] = request_data;
+    }
+
+    send_message(&mut socket, 6, message).await?;
+
+    let response = read_message(&mut socket, 7).await?;
+    let response: RawResponse = serde_json::from_value(response)?;
+
+    _ = socket.close(None).await;
+
+    Ok(response)
+}
+
+/// Sends a batch of requests to OBS, the requests are executed
+/// serially in order by OBS
+pub async fn send_batch(
+    auth: &Auth,
+    requests: Vec<RawRequest>,
+    halt_on_failure: bool,
+) -> Result<Vec<RawResponse>, RawError> {
+    let mut socket = connect(auth).await?;
+
+    send_message(
+        &mut socket,
+        8,
+        json!({
+            "requestId": REQUEST_ID,
+            "haltOnFailure": halt_on_failure,
+            "requests": requests,
+        }),
+    )
+    .await?;
+
+    let response = read_message(&mut socket, 9).await?;
+    let response: BatchResponse = serde_json::from_value(response)?;
+
+    _ = socket.close(None).await;
+
+    Ok(response.results)
+}
+
+/// Connects to OBS and completes the identification handshake
+async fn connect(auth: &Auth) -> Result<Socket, RawError> {
+    let url = format!("ws://{}:{}", auth.host, auth.port);
+    let (mut socket, _) = connect_async(url).await?;
+
+    let hello = read_message(&mut socket, 0).await?;
+    let hello: Hello = serde_json::from_value(hello)?;
+
+    let mut identify = json!({
+        "rpcVersion": hello.rpc_version,
+        // Raw connections don't need any events
+        "eventSubscriptions": 0,
+    });
+
+    if let Some(authentication) = hello.authentication {
+        identify["authentication"] = serde_json::Value::String(create_auth_response(
+            &authentication.challenge,
+            &authentication.salt,
+            &auth.password,
+        ));
+    }
+
+    send_message(&mut socket, 1, identify).await?;
+
+    read_message(&mut socket, 2)
+        .await
+        .map_err(|_| RawError::Handshake)?;
+
+    Ok(socket)
+}
+
+async fn send_message(
+    socket: &mut Socket,
+    op: u8,
+    data: serde_json::Value,
+) -> Result<(), RawError> {
+    let message = serde_json::to_string(&json!({ "op": op, "d": data }))?;
+    socket.send(Message::text(message)).await?;
+    Ok(())
+}
+
+/// Reads messages until one with the provided `op` code is received
+async fn read_message(socket: &mut Socket, op: u8) -> Result<serde_json::Value, RawError> {
+    loop {
+        let message = match timeout(READ_TIMEOUT, socket.next()).await {
+            Ok(Some(value)) => value?,
+            Ok(None) | Err(_) => return Err(RawError::Closed),
+        };
+
+        let text = match message {
+            Message::Text(value) => value,
+            Message::Close(_) => return Err(RawError::Closed),
+            _ => continue,
+        };
+
+        let message: ServerMessage = serde_json::from_str(text.as_str())?;
+        if message.op == op {
+            return Ok(message.d);
+        }
+    }
+}
+
+/// Creates the authentication string from the server challenge
+fn create_auth_response(challenge: &str, salt: &str, password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    hasher.update(salt.as_bytes());
+    let secret = general_purpose::STANDARD.encode(hasher.finalize_reset());
+
+    hasher.update(secret.as_bytes());
+    hasher.update(challenge.as_bytes());
+    general_purpose::STANDARD.encode(hasher.finalize())
+}
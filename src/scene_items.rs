@@ -0,0 +1,133 @@
+//! Scene item selection
+//!
+//! Scene items can be inside groups or nested scene sources, items are
+//! selected by the UUID of the scene (or group) that directly contains
+//! them along with their item ID
+
+use obws::{
+    requests::{scene_items::Source, scenes::SceneId},
+    responses::scene_items::{SceneItem, SourceType},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{messages::SelectOption, state::ObsError};
+
+/// Maximum depth of nested scenes to walk, protects against deeply
+/// nested (or recursive) scene setups
+const MAX_DEPTH: usize = 8;
+
+/// Scene item selected in the inspector
+#[derive(Deserialize)]
+pub struct SceneItemTarget {
+    /// UUID of the top level scene the item was selected from
+    pub scene: Option<String>,
+    /// UUID of the group or nested scene that contains the item, not
+    /// present when the item is directly in `scene`
+    #[serde(default)]
+    pub item_scene: Option<String>,
+    /// Whether the `item_scene` is a group rather than a scene
+    #[serde(default)]
+    pub item_group: bool,
+    /// ID of the scene item
+    pub item: Option<i64>,
+}
+
+impl SceneItemTarget {
+    /// Resolves the UUID of the scene containing the item and the item ID
+    pub fn resolve(&self) -> Option<(Uuid, i64)> {
+        let scene = self.item_scene.as_deref().or(self.scene.as_deref())?;
+        let scene = Uuid::parse_str(scene).ok()?;
+        let item = self.item?;
+        Some((scene, item))
+    }
+
+    /// Whether the item is inside a group, group items must be listed
+    /// with [list_items] as groups are rejected by `GetSceneItemList`,
+    /// other scene item requests accept either scenes or groups
+    pub fn in_group(&self) -> bool {
+        self.item_scene.is_some() && self.item_group
+    }
+}
+
+/// Lists the items directly inside a scene or group
+pub async fn list_items(
+    client: &obws::Client,
+    scene: Uuid,
+    group: bool,
+) -> Result<Vec<SceneItem>, ObsError> {
+    match group {
+        true => client.scene_items().list_group(SceneId::Uuid(scene)).await,
+        false => client.scene_items().list(SceneId::Uuid(scene)).await,
+    }
+}
+
+/// Lists the items in a scene along with the items inside any groups or
+/// nested scenes, children are listed directly after their parent with
+/// their labels indented
+pub async fn list_scene_item_tree(
+    client: &obws::Client,
+    scene: Uuid,
+) -> Result<Vec<SelectOption>, ObsError> {
+    let mut options = Vec::new();
+    let mut path = vec![scene];
+    let items = list_items(client, scene, false).await?;
+    collect_items(client, scene, false, items, &mut path, &mut options).await?;
+    Ok(options)
+}
+
+/// Adds the `items` of the scene (or group) and all their children to
+/// the `options`, `path` contains the scenes currently being walked
+async fn collect_items(
+    client: &obws::Client,
+    scene: Uuid,
+    group: bool,
+    items: Vec<SceneItem>,
+    path: &mut Vec<Uuid>,
+    options: &mut Vec<SelectOption>,
+) -> Result<(), ObsError> {
+    let depth = path.len() - 1;
+
+    // Items are listed top to bottom as they are in OBS
+    for item in items.into_iter().rev() {
+        options.push(SelectOption {
+            label: format!("{}{}", "    ".repeat(depth), item.source_name),
+            value: format!("{scene}:{}:{group}", item.id),
+        });
+
+        if item.source_type != SourceType::Scene || path.len() > MAX_DEPTH {
+            continue;
+        }
+
+        let source = client
+            .scene_items()
+            .source(Source {
+                scene: SceneId::Uuid(scene),
+                item_id: item.id,
+            })
+            .await?;
+
+        // Scenes can be added to other scenes more than once but a scene
+        // can't contain itself
+        if path.contains(&source.uuid) {
+            continue;
+        }
+
+        let is_group = item.is_group.unwrap_or_default();
+        let children = list_items(client, source.uuid, is_group).await?;
+
+        path.push(source.uuid);
+        Box::pin(collect_items(
+            client,
+            source.uuid,
+            is_group,
+            children,
+            path,
+            options,
+        ))
+        .await?;
+        path.pop();
+    }
+
+    Ok(())
+}
@@ -1,19 +1,24 @@
 use std::{
     cell::{Cell, RefCell},
+    collections::VecDeque,
     rc::Rc,
     time::Duration,
 };
 
+use futures_util::StreamExt;
 use obws::{
     client::{ConnectConfig, DEFAULT_BROADCAST_CAPACITY, HandshakeError},
+    events::Event,
     responses::WebSocketCloseCode,
 };
 use serde::{Deserialize, Serialize};
 use tilepad_plugin_sdk::{Inspector, tracing};
 use tokio::{
+    sync::broadcast,
     task::{JoinHandle, spawn_local},
     time::sleep,
 };
+use uuid::Uuid;
 
 use crate::messages::InspectorMessageOut;
 
@@ -38,9 +43,114 @@ pub struct Auth {
     pub password: String,
 }
 
-type ObsError = obws::error::Error;
+/// Named stream service configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StreamServicePreset {
+    pub name: String,
+    /// Stream service type (rtmp_custom or rtmp_common)
+    pub service_type: String,
+    /// Name of the service for rtmp_common (e.g Twitch)
+    pub service: Option<String>,
+    /// Server URL to stream to
+    pub server: String,
+    /// Stream key, never sent to the inspector
+    pub key: String,
+}
+
+/// Error from the OBS websocket client
+pub type ObsError = obws::error::Error;
 type ObsClient = obws::Client;
 
+/// Maximum number of previous program scenes to remember
+const MAX_SCENE_HISTORY: usize = 50;
+
+/// Event broadcast to background tasks
+#[derive(Clone)]
+pub enum ClientEvent {
+    /// Connection to OBS was established
+    Connected,
+    /// Event received from OBS
+    Obs(Event),
+}
+
+/// History of program scenes for returning to previous scenes
+#[derive(Default)]
+struct SceneHistory {
+    /// Current program scene
+    current: Option<Uuid>,
+    /// Scenes switched away from, most recent last
+    previous: VecDeque<Uuid>,
+    /// Scene being returned to, the history is only updated once the
+    /// switch to it has happened
+    returning_to: Option<Uuid>,
+    /// Number of scenes returned to in a row
+    returns: usize,
+}
+
+impl SceneHistory {
+    /// Clears the history when connecting to a new client
+    fn reset(&mut self, current: Option<Uuid>) {
+        *self = Self {
+            current,
+            ..Default::default()
+        };
+    }
+
+    /// Updates the history after the program scene changes
+    fn switched_to(&mut self, scene: Uuid) {
+        if self.returning_to.take() == Some(scene) && self.previous.back() == Some(&scene) {
+            self.previous.pop_back();
+            self.current = Some(scene);
+            self.returns += 1;
+            return;
+        }
+
+        if let Some(current) = self.current.replace(scene)
+            && current != scene
+        {
+            self.previous.push_back(current);
+            self.returns = 0;
+
+            if self.previous.len() > MAX_SCENE_HISTORY {
+                self.previous.pop_front();
+            }
+        }
+    }
+
+    /// Removes a deleted scene from the history
+    fn remove(&mut self, scene: Uuid) {
+        self.previous.retain(|previous| *previous != scene);
+    }
+
+    /// Starts returning to the most recent previous scene, unless `depth`
+    /// scenes have already been returned to in a row
+    fn start_return(&mut self, depth: usize) -> Option<Uuid> {
+        if self.returns >= depth {
+            return None;
+        }
+
+        let scene = *self.previous.back()?;
+        self.returning_to = Some(scene);
+        Some(scene)
+    }
+
+    /// Stops returning to the `scene` after the switch failed
+    fn cancel_return(&mut self, scene: Uuid) {
+        if self.returning_to == Some(scene) {
+            self.returning_to = None;
+        }
+    }
+}
+
+/// Channel for broadcasting [ClientEvent]s
+struct EventChannel(broadcast::Sender<ClientEvent>);
+
+impl Default for EventChannel {
+    fn default() -> Self {
+        Self(broadcast::channel(DEFAULT_BROADCAST_CAPACITY).0)
+    }
+}
+
 #[derive(Default)]
 pub struct State {
     /// Current client state
@@ -58,6 +168,15 @@ pub struct State {
 
     /// Handle to a retry task that is attempting to reconnect
     connect_retry_task: RefCell<Option<JoinHandle<()>>>,
+
+    /// Channel for broadcasting events to background tasks
+    events: EventChannel,
+
+    /// Handle to the task forwarding events from the current client
+    event_task: RefCell<Option<JoinHandle<()>>>,
+
+    /// Program scene history, updated from the forwarded events
+    scene_history: Rc<RefCell<SceneHistory>>,
 }
 
 impl State {
@@ -77,6 +196,32 @@ impl State {
         }
     }
 
+    /// Subscribe to events from the current and any future clients
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ClientEvent> {
+        self.events.0.subscribe()
+    }
+
+    /// Gets the previous program scene to return to, at most `depth`
+    /// scenes can be returned to in a row. The history is updated when
+    /// the program scene change is received
+    pub fn start_previous_scene(&self, depth: usize) -> Option<Uuid> {
+        self.scene_history.borrow_mut().start_return(depth)
+    }
+
+    /// Cancels returning to the previous `scene` when switching failed
+    pub fn cancel_previous_scene(&self, scene: Uuid) {
+        self.scene_history.borrow_mut().cancel_return(scene);
+    }
+
+    /// Get the authentication used by the current connection
+    pub fn get_auth(&self) -> Option<Auth> {
+        if !matches!(self.get_state(), ClientState::Connected) {
+            return None;
+        }
+
+        self.current_auth.borrow().clone()
+    }
+
     // Run some action on the client
     pub fn run_with_client<F>(self: Rc<State>, action: F)
     where
@@ -180,19 +325,69 @@ impl State {
             }
         };
 
+        let events = client.events();
+
+        // History from a previous connection may not be for the same OBS
+        let current_scene = match client.scenes().current_program_scene().await {
+            Ok(value) => Some(value.id.uuid),
+            Err(cause) => {
+                tracing::error!(?cause, "failed to get current scene");
+                None
+            }
+        };
+        self.scene_history.borrow_mut().reset(current_scene);
+
         let mut client_lock = self.client.lock().await;
         *client_lock = Some(client);
+        drop(client_lock);
 
         // Persist the current credentials
         self.current_auth.replace(Some(auth));
         self.set_state(ClientState::Connected);
 
+        match events {
+            Ok(events) => self.forward_events(events),
+            Err(cause) => tracing::error!(?cause, "failed to subscribe to events"),
+        }
+
+        _ = self.events.0.send(ClientEvent::Connected);
+
         Ok(())
     }
 
+    /// Forwards events from the current client to the event channel
+    fn forward_events<S>(&self, events: S)
+    where
+        S: futures_util::Stream<Item = Event> + 'static,
+    {
+        let sender = self.events.0.clone();
+        let scene_history = self.scene_history.clone();
+        let handle = spawn_local(async move {
+            let mut events = std::pin::pin!(events);
+            while let Some(event) = events.next().await {
+                match &event {
+                    Event::CurrentProgramSceneChanged { id } => {
+                        scene_history.borrow_mut().switched_to(id.uuid);
+                    }
+                    Event::SceneRemoved { id, .. } => {
+                        scene_history.borrow_mut().remove(id.uuid);
+                    }
+                    _ => {}
+                }
+
+                _ = sender.send(ClientEvent::Obs(event));
+            }
+        });
+
+        // Stop forwarding from any previous client
+        if let Some(task) = self.event_task.replace(Some(handle)) {
+            task.abort();
+        }
+    }
+
     // Execute an action with the client, handles updating the client state
     // in the event of a disconnect or error
-    async fn execute_with_client<F, O>(self: Rc<Self>, action: F) -> Result<Option<O>, ObsError>
+    pub async fn execute_with_client<F, O>(self: Rc<Self>, action: F) -> Result<Option<O>, ObsError>
     where
         F: for<'a> AsyncFnOnce(&'a mut obws::Client) -> Result<O, ObsError>,
         F: 'static,
@@ -210,6 +405,7 @@ impl State {
             Err(err) => {
                 let mut reset = false;
 
+                #[allow(clippy::collapsible_match)]
                 match &err {
                     ObsError::Handshake(HandshakeError::ConnectionClosed(details)) => {
                         // Handle authentication failure
@@ -258,3 +454,134 @@ impl State {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{MAX_SCENE_HISTORY, SceneHistory};
+
+    fn scene(id: u128) -> Uuid {
+        Uuid::from_u128(id)
+    }
+
+    /// Creates a history that has switched through the `scenes` in order
+    fn history(scenes: &[u128]) -> SceneHistory {
+        let mut history = SceneHistory::default();
+        for id in scenes {
+            history.switched_to(scene(*id));
+        }
+        history
+    }
+
+    /// Returns to the previous scene as if the switch succeeded
+    fn go_back(history: &mut SceneHistory, depth: usize) -> Option<Uuid> {
+        let previous = history.start_return(depth)?;
+        history.switched_to(previous);
+        Some(previous)
+    }
+
+    #[test]
+    fn returns_through_previous_scenes() {
+        let mut history = history(&[1, 2, 3]);
+
+        assert_eq!(go_back(&mut history, 10), Some(scene(2)));
+        assert_eq!(go_back(&mut history, 10), Some(scene(1)));
+        assert_eq!(go_back(&mut history, 10), None);
+        assert_eq!(history.current, Some(scene(1)));
+    }
+
+    #[test]
+    fn switching_to_the_same_scene_is_not_recorded() {
+        let mut history = history(&[1, 1, 2, 2]);
+
+        assert_eq!(go_back(&mut history, 10), Some(scene(1)));
+        assert_eq!(go_back(&mut history, 10), None);
+    }
+
+    #[test]
+    fn failed_return_keeps_history() {
+        let mut history = history(&[1, 2]);
+
+        let previous = history.start_return(10);
+        assert_eq!(previous, Some(scene(1)));
+        history.cancel_return(scene(1));
+
+        assert_eq!(history.start_return(10), Some(scene(1)));
+        assert_eq!(history.previous.len(), 1);
+    }
+
+    #[test]
+    fn switch_after_failed_return_is_recorded() {
+        let mut history = history(&[1, 2]);
+
+        history.start_return(10);
+        history.cancel_return(scene(1));
+
+        // Switching to the scene normally records the scene it came from
+        history.switched_to(scene(1));
+        assert_eq!(history.previous, [scene(1), scene(2)]);
+    }
+
+    #[test]
+    fn other_switch_while_returning_is_recorded() {
+        let mut history = history(&[1, 2]);
+
+        history.start_return(10);
+        history.switched_to(scene(3));
+
+        assert_eq!(history.previous, [scene(1), scene(2)]);
+        assert_eq!(history.returning_to, None);
+    }
+
+    #[test]
+    fn depth_limits_returns_without_removing_history() {
+        let mut history = history(&[1, 2, 3, 4]);
+
+        assert_eq!(go_back(&mut history, 1), Some(scene(3)));
+        assert_eq!(history.start_return(1), None);
+
+        // History is still available for a larger depth
+        assert_eq!(go_back(&mut history, 10), Some(scene(2)));
+        assert_eq!(go_back(&mut history, 10), Some(scene(1)));
+    }
+
+    #[test]
+    fn switching_resets_depth() {
+        let mut history = history(&[1, 2, 3]);
+
+        assert_eq!(go_back(&mut history, 1), Some(scene(2)));
+        history.switched_to(scene(4));
+
+        assert_eq!(go_back(&mut history, 1), Some(scene(2)));
+    }
+
+    #[test]
+    fn removed_scenes_are_skipped() {
+        let mut history = history(&[1, 2, 3]);
+        history.remove(scene(2));
+
+        assert_eq!(go_back(&mut history, 10), Some(scene(1)));
+    }
+
+    #[test]
+    fn history_is_limited() {
+        let scenes: Vec<u128> = (0..(MAX_SCENE_HISTORY as u128 + 10)).collect();
+        let history = history(&scenes);
+
+        assert_eq!(history.previous.len(), MAX_SCENE_HISTORY);
+        assert_eq!(
+            history.previous.back(),
+            Some(&scene(MAX_SCENE_HISTORY as u128 + 8))
+        );
+    }
+
+    #[test]
+    fn reset_clears_history() {
+        let mut history = history(&[1, 2]);
+        history.reset(Some(scene(3)));
+
+        assert_eq!(history.start_return(10), None);
+        assert_eq!(history.current, Some(scene(3)));
+    }
+}
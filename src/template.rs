@@ -0,0 +1,159 @@
+//! Simple `{variable}` templates used for file names, labels and text
+//!
+//! Variables can optionally include a format after a colon which is used
+//! for date and time variables (e.g `{date:%d-%m-%Y}`)
+
+use std::fmt::Write;
+
+use chrono::{DateTime, Local};
+use tilepad_plugin_sdk::tracing;
+
+use crate::state::ObsError;
+
+/// Default format for the `{date}` variable
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Default format for the `{time}` variable, avoids colons so that
+/// it can be used in file names
+const DEFAULT_TIME_FORMAT: &str = "%H-%M-%S";
+
+/// Renders the `template` replacing any variables with the value provided
+/// by `resolve`, unknown variables are left as-is
+///
+/// `resolve` is given the variable name and the optional format
+pub fn render<F>(template: &str, mut resolve: F) -> String
+where
+    F: FnMut(&str, Option<&str>) -> Option<String>,
+{
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = match rest.find('}') {
+            Some(value) => value,
+            None => break,
+        };
+
+        let variable = &rest[1..end];
+        let (name, format) = match variable.split_once(':') {
+            Some((name, format)) => (name.trim(), Some(format)),
+            None => (variable.trim(), None),
+        };
+
+        match resolve(name, format) {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[..=end]),
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Renders a `template` that can use the current `{scene}`, recording
+/// `{elapsed}` time and stream `{uptime}` along with the date and time,
+/// `resolve` provides any additional variables
+///
+/// Values are only requested from OBS when the template uses them
+pub async fn render_with_obs<F>(
+    client: &obws::Client,
+    template: &str,
+    mut resolve: F,
+) -> Result<String, ObsError>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let scene = if template.contains("{scene") {
+        match client.scenes().current_program_scene().await {
+            Ok(value) => Some(value.id.name),
+            Err(cause) => {
+                tracing::error!(?cause, "failed to get current scene");
+                return Err(cause);
+            }
+        }
+    } else {
+        None
+    };
+
+    let elapsed = if template.contains("{elapsed") {
+        match client.recording().status().await {
+            Ok(value) => Some(format_timecode(value.duration.whole_seconds())),
+            Err(cause) => {
+                tracing::error!(?cause, "failed to get recording status");
+                return Err(cause);
+            }
+        }
+    } else {
+        None
+    };
+
+    let uptime = if template.contains("{uptime") {
+        match client.streaming().status().await {
+            Ok(value) => Some(format_timecode(value.duration.whole_seconds())),
+            Err(cause) => {
+                tracing::error!(?cause, "failed to get stream status");
+                return Err(cause);
+            }
+        }
+    } else {
+        None
+    };
+
+    let now = Local::now();
+    Ok(render(template, |variable, format| match variable {
+        "scene" => scene.clone(),
+        "elapsed" => elapsed.clone(),
+        "uptime" => uptime.clone(),
+        _ => resolve(variable).or_else(|| resolve_date_time(&now, variable, format)),
+    }))
+}
+
+/// Resolves the date and time variables available to every template
+pub fn resolve_date_time(
+    now: &DateTime<Local>,
+    name: &str,
+    format: Option<&str>,
+) -> Option<String> {
+    let format = match name {
+        "date" => format.unwrap_or(DEFAULT_DATE_FORMAT),
+        "time" => format.unwrap_or(DEFAULT_TIME_FORMAT),
+        "datetime" => format.unwrap_or("%Y-%m-%d_%H-%M-%S"),
+        "timestamp" => return Some(now.timestamp().to_string()),
+        _ => return None,
+    };
+
+    let mut output = String::new();
+
+    // Invalid user provided formats produce an error while writing
+    write!(output, "{}", now.format(format)).ok()?;
+
+    Some(output)
+}
+
+/// Replaces characters that are not allowed within file names
+pub fn sanitize_file_name(value: &str) -> String {
+    value
+        .chars()
+        .map(|char| match char {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            char if char.is_control() => '_',
+            char => char,
+        })
+        .collect()
+}
+
+/// Formats a number of seconds as a HH:MM:SS timecode
+pub fn format_timecode(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
@@ -0,0 +1,132 @@
+//! Tasks started by pressing tiles
+//!
+//! Each tile has at most one running task which is stopped by pressing
+//! the tile again, tasks are also stopped when the properties of their
+//! tile change or the tile is removed
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Duration};
+
+use tilepad_plugin_sdk::{JsonObject, PluginSessionHandle, TileId, TileModel};
+use tokio::{
+    task::{JoinHandle, spawn_local},
+    time::timeout,
+};
+
+/// Maximum time to wait for the properties of a tile that isn't visible,
+/// tiles that were removed may never respond
+const TILE_PROPERTIES_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Running tasks started from tiles
+#[derive(Default, Clone)]
+pub struct TileTasks {
+    tasks: Rc<RefCell<HashMap<TileId, TileTask>>>,
+}
+
+struct TileTask {
+    /// Properties of the tile when the task was started
+    properties: JsonObject,
+    /// Whether the tile was visible at the last update
+    visible: bool,
+    /// Handle to the running task
+    handle: JoinHandle<()>,
+}
+
+impl TileTasks {
+    /// Starts the `task` for the tile, or stops the running task if the
+    /// tile already has one, returns whether the task was started
+    pub fn toggle<F>(&self, tile_id: TileId, properties: serde_json::Value, task: F) -> bool
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        let mut tasks = self.tasks.borrow_mut();
+
+        if let Some(task) = tasks.remove(&tile_id)
+            && !task.handle.is_finished()
+        {
+            task.handle.abort();
+            return false;
+        }
+
+        let properties = match properties {
+            serde_json::Value::Object(value) => value,
+            _ => JsonObject::default(),
+        };
+
+        tasks.insert(
+            tile_id,
+            TileTask {
+                properties,
+                visible: true,
+                handle: spawn_local(task),
+            },
+        );
+
+        true
+    }
+
+    /// Stops the tasks of visible `tiles` whose properties have changed,
+    /// tiles that are no longer visible are checked in case they were
+    /// removed or changed while hidden
+    pub fn update(&self, session: &PluginSessionHandle, tiles: &[TileModel]) {
+        self.tasks.borrow_mut().retain(|tile_id, task| {
+            if task.handle.is_finished() {
+                return false;
+            }
+
+            let tile = match tiles.iter().find(|tile| tile.id.eq(tile_id)) {
+                Some(value) => value,
+
+                // Tile may be in a folder that isn't currently shown
+                None => {
+                    if task.visible {
+                        task.visible = false;
+                        spawn_local(self.clone().check_hidden(session.clone(), *tile_id));
+                    }
+
+                    return true;
+                }
+            };
+
+            task.visible = true;
+
+            if task.properties != tile.properties {
+                task.handle.abort();
+                return false;
+            }
+
+            true
+        });
+    }
+
+    /// Stops the task of a tile that is no longer visible if the tile was
+    /// removed or its properties have changed
+    async fn check_hidden(self, session: PluginSessionHandle, tile_id: TileId) {
+        let properties = get_hidden_tile_properties(&session, tile_id).await;
+
+        let mut tasks = self.tasks.borrow_mut();
+        let changed = tasks
+            .get(&tile_id)
+            .is_some_and(|task| !task.visible && properties.as_ref() != Some(&task.properties));
+
+        if changed && let Some(task) = tasks.remove(&tile_id) {
+            task.handle.abort();
+        }
+    }
+}
+
+/// Gets the current properties of a tile that isn't visible, [None] when
+/// the tile no longer exists
+pub async fn get_hidden_tile_properties(
+    session: &PluginSessionHandle,
+    tile_id: TileId,
+) -> Option<JsonObject> {
+    match timeout(
+        TILE_PROPERTIES_TIMEOUT,
+        session.get_tile_properties(tile_id),
+    )
+    .await
+    {
+        Ok(Ok(serde_json::Value::Object(properties))) => Some(properties),
+        _ => None,
+    }
+}
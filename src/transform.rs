@@ -0,0 +1,264 @@
+//! Scene item transform presets
+//!
+//! Presets are stored in the tile properties and only contain the parts of
+//! the transform that should be changed when the preset is applied, presets
+//! can optionally be animated to from the current transform
+
+use std::{rc::Rc, time::Duration};
+
+use obws::{
+    common::BoundsType,
+    requests::{
+        scene_items::{Bounds, Crop, Position, Scale, SceneItemTransform, SetTransform},
+        scenes::SceneId,
+    },
+    responses::scene_items::SceneItemTransform as CurrentTransform,
+};
+use serde::{Deserialize, Serialize};
+use tilepad_plugin_sdk::{TileId, tracing};
+use tokio::time::{Instant, sleep_until};
+use uuid::Uuid;
+
+use crate::{action::Easing, state::State, tile_tasks::TileTasks};
+
+/// Scene item transform, values that are not provided are left unchanged
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct TransformPreset {
+    pub position_x: Option<f32>,
+    pub position_y: Option<f32>,
+    /// Clockwise rotation in degrees
+    pub rotation: Option<f32>,
+    pub scale_x: Option<f32>,
+    pub scale_y: Option<f32>,
+    pub bounds_type: Option<BoundsType>,
+    pub bounds_width: Option<f32>,
+    pub bounds_height: Option<f32>,
+    /// Pixels cropped from each side before scaling
+    pub crop_left: Option<u32>,
+    pub crop_right: Option<u32>,
+    pub crop_top: Option<u32>,
+    pub crop_bottom: Option<u32>,
+}
+
+impl TransformPreset {
+    /// Creates a preset from the current transform of a scene item
+    pub fn from_current(current: &CurrentTransform) -> Self {
+        // Bounds sizes are only meaningful (and valid) when bounds are enabled
+        let (bounds_width, bounds_height) = match current.bounds_type {
+            BoundsType::None => (None, None),
+            _ => (Some(current.bounds_width), Some(current.bounds_height)),
+        };
+
+        Self {
+            position_x: Some(current.position_x),
+            position_y: Some(current.position_y),
+            rotation: Some(current.rotation),
+            scale_x: Some(current.scale_x),
+            scale_y: Some(current.scale_y),
+            bounds_type: Some(current.bounds_type),
+            bounds_width,
+            bounds_height,
+            crop_left: Some(current.crop_left),
+            crop_right: Some(current.crop_right),
+            crop_top: Some(current.crop_top),
+            crop_bottom: Some(current.crop_bottom),
+        }
+    }
+
+    /// Whether the preset doesn't change any part of the transform
+    pub fn is_empty(&self) -> bool {
+        self.position_x.is_none()
+            && self.position_y.is_none()
+            && self.rotation.is_none()
+            && self.scale_x.is_none()
+            && self.scale_y.is_none()
+            && self.bounds_type.is_none()
+            && self.bounds_width.is_none()
+            && self.bounds_height.is_none()
+            && self.crop_left.is_none()
+            && self.crop_right.is_none()
+            && self.crop_top.is_none()
+            && self.crop_bottom.is_none()
+    }
+
+    /// Creates the request transform for applying the preset
+    pub fn to_request(&self) -> SceneItemTransform {
+        SceneItemTransform {
+            position: Some(Position {
+                x: self.position_x,
+                y: self.position_y,
+            }),
+            rotation: self.rotation,
+            scale: Some(Scale {
+                x: self.scale_x,
+                y: self.scale_y,
+            }),
+            alignment: None,
+            bounds: Some(Bounds {
+                r#type: self.bounds_type,
+                alignment: None,
+                width: self.bounds_width,
+                height: self.bounds_height,
+            }),
+            crop: Some(Crop {
+                left: self.crop_left,
+                right: self.crop_right,
+                top: self.crop_top,
+                bottom: self.crop_bottom,
+            }),
+        }
+    }
+
+    /// Interpolates between this transform and the `target`, values not
+    /// present in the `target` are left unchanged
+    fn interpolate(&self, target: &TransformPreset, t: f32) -> TransformPreset {
+        TransformPreset {
+            position_x: lerp(self.position_x, target.position_x, t),
+            position_y: lerp(self.position_y, target.position_y, t),
+            rotation: lerp(self.rotation, target.rotation, t),
+            scale_x: lerp(self.scale_x, target.scale_x, t),
+            scale_y: lerp(self.scale_y, target.scale_y, t),
+            // Bounds type can't be interpolated so it changes immediately
+            bounds_type: target.bounds_type,
+            bounds_width: lerp(self.bounds_width, target.bounds_width, t),
+            bounds_height: lerp(self.bounds_height, target.bounds_height, t),
+            crop_left: lerp_pixels(self.crop_left, target.crop_left, t),
+            crop_right: lerp_pixels(self.crop_right, target.crop_right, t),
+            crop_top: lerp_pixels(self.crop_top, target.crop_top, t),
+            crop_bottom: lerp_pixels(self.crop_bottom, target.crop_bottom, t),
+        }
+    }
+}
+
+/// Starts animating the scene item to the `target` transform, or cancels
+/// the animation if the tile already has one running
+pub fn toggle_transform_animation(
+    tasks: &TileTasks,
+    state: Rc<State>,
+    tile_id: TileId,
+    properties: serde_json::Value,
+    animation: TransformAnimation,
+) {
+    // Item is left wherever a cancelled animation had reached
+    tasks.toggle(tile_id, properties, animate_transform(state, animation));
+}
+
+/// Animation of a scene item to a target transform
+pub struct TransformAnimation {
+    pub scene: Uuid,
+    pub item_id: i64,
+    pub target: TransformPreset,
+    pub duration: Duration,
+    pub easing: Easing,
+    /// Time between transform updates
+    pub frame_interval: Duration,
+}
+
+/// Interpolates the scene item from its current transform to the target,
+/// sending a transform update for each frame of the animation
+async fn animate_transform(state: Rc<State>, animation: TransformAnimation) {
+    let TransformAnimation {
+        scene,
+        item_id,
+        target,
+        duration,
+        easing,
+        frame_interval,
+    } = animation;
+
+    let current = state
+        .clone()
+        .execute_with_client(async move |client| {
+            client
+                .scene_items()
+                .transform(SceneId::Uuid(scene), item_id)
+                .await
+        })
+        .await;
+
+    let start = match current {
+        Ok(Some(value)) => TransformPreset::from_current(&value),
+        Ok(None) => return,
+        Err(cause) => {
+            tracing::error!(?cause, "failed to get scene item transform");
+            return;
+        }
+    };
+
+    let started = Instant::now();
+    let mut next_frame = started;
+
+    loop {
+        let elapsed = started.elapsed();
+        let progress = match duration.is_zero() {
+            true => 1.0,
+            false => (elapsed.as_secs_f32() / duration.as_secs_f32()).min(1.0),
+        };
+
+        let transform = start.interpolate(&target, ease(easing, progress));
+        let result = state
+            .clone()
+            .execute_with_client(async move |client| {
+                client
+                    .scene_items()
+                    .set_transform(SetTransform {
+                        scene: SceneId::Uuid(scene),
+                        item_id,
+                        transform: transform.to_request(),
+                    })
+                    .await
+            })
+            .await;
+
+        match result {
+            Ok(Some(_)) => {}
+            Ok(None) => return,
+            Err(cause) => {
+                tracing::error!(?cause, "failed to set scene item transform");
+                return;
+            }
+        }
+
+        if progress >= 1.0 {
+            return;
+        }
+
+        // Frames are skipped rather than queued when requests are slow
+        next_frame = (next_frame + frame_interval).max(Instant::now());
+        sleep_until(next_frame).await;
+    }
+}
+
+/// Linear interpolation between `from` and `to`, jumps straight to `to`
+/// when there is no starting value
+fn lerp(from: Option<f32>, to: Option<f32>, t: f32) -> Option<f32> {
+    match (from, to) {
+        (Some(from), Some(to)) => Some(from + (to - from) * t),
+        (_, to) => to,
+    }
+}
+
+fn lerp_pixels(from: Option<u32>, to: Option<u32>, t: f32) -> Option<u32> {
+    lerp(
+        from.map(|value| value as f32),
+        to.map(|value| value as f32),
+        t,
+    )
+    .map(|value| value.round().max(0.0) as u32)
+}
+
+/// Applies the `easing` curve to the linear progress `t` (0.0 to 1.0)
+fn ease(easing: Easing, t: f32) -> f32 {
+    match easing {
+        Easing::Linear => t,
+        Easing::EaseIn => t * t * t,
+        Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+        Easing::EaseInOut => {
+            if t < 0.5 {
+                4.0 * t * t * t
+            } else {
+                1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+            }
+        }
+    }
+}